@@ -0,0 +1,38 @@
+//! Compares the framebuffer dimensions cached by the facade with querying SDL every time.
+//!
+//! Run with `cargo bench`. Set `SDL_VIDEODRIVER=offscreen` to run without a display.
+
+extern crate glium;
+extern crate glium_sdl2;
+extern crate sdl2;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use glium_sdl2::DisplayBuild;
+
+const ITERATIONS: u32 = 1_000_000;
+
+fn bench<F: FnMut() -> (u32, u32)>(name: &str, mut f: F) {
+	// warm up
+	for _ in 0..ITERATIONS / 10 {
+		black_box(f());
+	}
+
+	let start = Instant::now();
+	for _ in 0..ITERATIONS {
+		black_box(f());
+	}
+	let elapsed = start.elapsed();
+
+	println!("{:<8} {:>8.1} ns/iter", name, elapsed.as_secs_f64() * 1e9 / f64::from(ITERATIONS));
+}
+
+fn main() {
+	let sdl_context = sdl2::init().unwrap();
+	let video = sdl_context.video().unwrap();
+	let display = video.window("glium_sdl2 bench", 800, 600).hidden().build_glium().unwrap();
+
+	bench("cached", || display.get_framebuffer_dimensions());
+	bench("direct", || display.drawable_size());
+}
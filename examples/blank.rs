@@ -26,6 +26,8 @@ fn main() {
 		for event in event_pump.poll_iter() {
 			use sdl2::event::Event;
 
+			display.handle_event(&event);
+
 			match event {
 				Event::Quit { .. } => {
 					running = false;
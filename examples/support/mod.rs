@@ -1,50 +1,14 @@
 #![allow(dead_code)]
 
-extern crate clock_ticks;
 extern crate genmesh;
 extern crate obj;
 
 use glium;
 use glium::vertex::VertexBufferAny;
 use glium_sdl2::Display;
-use std::thread;
-use std::time::Duration;
 
 pub mod camera;
 
-pub enum Action {
-	Stop,
-	Continue,
-}
-
-pub fn start_loop<F>(mut callback: F)
-where
-	F: FnMut() -> Action,
-{
-	let mut accumulator = 0;
-	let mut previous_clock = clock_ticks::precise_time_ns();
-
-	loop {
-		match callback() {
-			Action::Stop => break,
-			Action::Continue => (),
-		};
-
-		let now = clock_ticks::precise_time_ns();
-		accumulator += now - previous_clock;
-		previous_clock = now;
-
-		const FIXED_TIME_STAMP: u64 = 16666667;
-		while accumulator >= FIXED_TIME_STAMP {
-			accumulator -= FIXED_TIME_STAMP;
-
-			// if you have a game, update the state here
-		}
-
-		thread::sleep(Duration::from_millis(((FIXED_TIME_STAMP - accumulator) / 1000000) as u64));
-	}
-}
-
 /// Returns a vertex buffer that should be rendered as `TrianglesList`.
 pub fn load_wavefront(display: &Display, data: &[u8]) -> VertexBufferAny {
 	#[derive(Copy, Clone)]
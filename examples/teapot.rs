@@ -5,6 +5,9 @@ extern crate glium_sdl2;
 extern crate sdl2;
 
 use glium::Surface;
+use glium_sdl2::{Game, GameLoop, LoopControl};
+use sdl2::event::Event;
+use std::time::Duration;
 
 mod support;
 
@@ -129,19 +132,39 @@ fn main() {
 	)
 	.unwrap();
 
-	//
-	let mut camera = support::camera::CameraState::new();
+	let mut teapot = Teapot { vertex_buffer, program, camera: support::camera::CameraState::new() };
 
 	let mut event_pump = sdl_context.event_pump().unwrap();
 
 	// the main loop
-	support::start_loop(|| {
-		camera.update();
+	GameLoop::new(60).run(&display, &mut event_pump, &mut teapot).unwrap();
+}
+
+struct Teapot {
+	vertex_buffer: glium::vertex::VertexBufferAny,
+	program: glium::Program,
+	camera: support::camera::CameraState,
+}
+
+impl Game for Teapot {
+	fn event(&mut self, event: &Event) -> LoopControl {
+		match *event {
+			Event::Quit { .. } => return LoopControl::Stop,
+			ref ev => self.camera.process_input(ev),
+		}
+
+		LoopControl::Continue
+	}
 
+	fn update(&mut self, _timestep: Duration) {
+		self.camera.update();
+	}
+
+	fn render(&mut self, target: &mut glium::Frame, _alpha: f64) {
 		// building the uniforms
 		let uniforms = uniform! {
-				persp_matrix: camera.get_perspective(),
-				view_matrix: camera.get_view(),
+				persp_matrix: self.camera.get_perspective(),
+				view_matrix: self.camera.get_view(),
 		};
 
 		// draw parameters
@@ -151,30 +174,15 @@ fn main() {
 		};
 
 		// drawing a frame
-		let mut target = display.draw();
-
 		target.clear_color_and_depth((0.0, 0.0, 0.0, 0.0), 1.0);
 		target
 			.draw(
-				&vertex_buffer,
+				&self.vertex_buffer,
 				&glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
-				&program,
+				&self.program,
 				&uniforms,
 				&params,
 			)
 			.unwrap();
-		target.finish().unwrap();
-
-		// polling and handling the events received by the window
-		for event in event_pump.poll_iter() {
-			use sdl2::event::Event;
-
-			match event {
-				Event::Quit { .. } => return support::Action::Stop,
-				ev => camera.process_input(&ev),
-			}
-		}
-
-		support::Action::Continue
-	});
+	}
 }
@@ -71,6 +71,8 @@ fn main() {
 	let (mut t, mut nextFrameInstant) = (-0.5_f32, Instant::now() + frameDuration);
 	loop {
 		for event in eventPump.poll_iter() {
+			display.handle_event(&event);
+
 			match event {
 				Event::Quit { .. } | Event::KeyDown { scancode: Some(Scancode::Escape), .. } => return,
 				_ => {}
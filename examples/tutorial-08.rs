@@ -77,6 +77,8 @@ fn main() {
 	let mut nextFrameInstant = Instant::now() + frameDuration;
 	loop {
 		for event in eventPump.poll_iter() {
+			display.handle_event(&event);
+
 			match event {
 				Event::Quit { .. } | Event::KeyDown { scancode: Some(Scancode::Escape), .. } => return,
 				_ => {}
@@ -0,0 +1,475 @@
+//! Typed OpenGL context configuration, applied around a single window creation.
+
+use std::fmt;
+use std::os::raw::c_int;
+
+use glium::backend::Context;
+use glium::{Api, CapabilitiesSource, Profile, Version};
+use sdl2::sys;
+use sdl2::sys::SDL_GLattr;
+use sdl2::video::GLProfile;
+
+/// The OpenGL context and framebuffer settings to build a facade with.
+///
+/// Setting `GLAttr` values changes SDL's global state, which then applies to every window
+/// created afterwards. A `GlConfig` passed to `DisplayBuild::build_glium_with` sets the
+/// attributes of the window being built, then puts the context attributes (version, profile,
+/// flags, sRGB...) back to their previous values.
+///
+/// SDL can't report the framebuffer attributes that were requested (depth, stencil,
+/// multisampling, double buffering), so those are put back to SDL's defaults afterwards,
+/// whatever they were before. The attributes that aren't part of the configuration, such as the
+/// sizes of the color channels, are left as they were.
+///
+/// The default configuration matches SDL's defaults.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GlConfig {
+	/// The OpenGL (or OpenGL ES) version to request, or `None` to keep the one set on the
+	/// video subsystem, which is SDL's default unless changed.
+	pub version: Option<(u8, u8)>,
+
+	/// The context profile to request, or `None` to keep the one set on the video subsystem,
+	/// which lets the driver pick unless changed.
+	pub profile: Option<GLProfile>,
+
+	/// Whether to request a debug context. Defaults to `false`.
+	pub debug: bool,
+
+	/// Whether to request a forward-compatible context. Defaults to `false`.
+	pub forward_compatible: bool,
+
+	/// Whether to request a robust context that reports GPU resets.
+	/// See `request_robust_context`. Defaults to `false`.
+	pub robust: bool,
+
+	/// Whether to request isolation from resets caused by other applications.
+	/// Only used if `robust` is set. Defaults to `false`.
+	pub reset_isolation: bool,
+
+	/// The minimum number of bits in the depth buffer. Defaults to 16.
+	pub depth_bits: u8,
+
+	/// The minimum number of bits in the stencil buffer. Defaults to 0.
+	pub stencil_bits: u8,
+
+	/// The number of samples used for multisample anti-aliasing, or 0 to disable it.
+	/// Defaults to 0.
+	pub multisample_samples: u8,
+
+	/// Whether to request an sRGB-capable framebuffer. Defaults to `false`.
+	pub srgb: bool,
+
+	/// Whether the framebuffer is double buffered. Defaults to `true`.
+	pub double_buffer: bool,
+
+	/// Whether to build the window with `allow_highdpi`, so that its framebuffer has the
+	/// display's full resolution. Mouse events stay in window units, which can be converted
+	/// with `SDL2Facade::to_drawable_point`. Defaults to `false`.
+	pub high_dpi: bool,
+}
+
+impl Default for GlConfig {
+	fn default() -> GlConfig {
+		GlConfig {
+			version: None,
+			profile: None,
+			debug: false,
+			forward_compatible: false,
+			robust: false,
+			reset_isolation: false,
+			depth_bits: 16,
+			stencil_bits: 0,
+			multisample_samples: 0,
+			srgb: false,
+			double_buffer: true,
+			high_dpi: false,
+		}
+	}
+}
+
+impl GlConfig {
+	/// Sets SDL's OpenGL attributes from this configuration.
+	///
+	/// Attributes that aren't part of the configuration are left as they are.
+	pub(crate) fn apply(&self) -> Result<(), String> {
+		if let Some((major, minor)) = self.version {
+			set_attribute(SDL_GLattr::SDL_GL_CONTEXT_MAJOR_VERSION, major.into())?;
+			set_attribute(SDL_GLattr::SDL_GL_CONTEXT_MINOR_VERSION, minor.into())?;
+		}
+
+		if let Some(profile) = self.profile {
+			let mask = match profile {
+				GLProfile::Core => 1,
+				GLProfile::Compatibility => 2,
+				GLProfile::GLES => 4,
+				GLProfile::Unknown(mask) => mask,
+			};
+			set_attribute(SDL_GLattr::SDL_GL_CONTEXT_PROFILE_MASK, mask)?;
+		}
+
+		let mut flags = 0;
+		if self.debug {
+			flags |= CONTEXT_DEBUG_FLAG;
+		}
+		if self.forward_compatible {
+			flags |= CONTEXT_FORWARD_COMPATIBLE_FLAG;
+		}
+		if self.robust {
+			flags |= CONTEXT_ROBUST_ACCESS_FLAG;
+			if self.reset_isolation {
+				flags |= CONTEXT_RESET_ISOLATION_FLAG;
+			}
+		}
+		set_attribute(SDL_GLattr::SDL_GL_CONTEXT_FLAGS, flags)?;
+		set_reset_notification(self.robust)?;
+
+		set_attribute(SDL_GLattr::SDL_GL_DEPTH_SIZE, self.depth_bits.into())?;
+		set_attribute(SDL_GLattr::SDL_GL_STENCIL_SIZE, self.stencil_bits.into())?;
+		set_attribute(SDL_GLattr::SDL_GL_MULTISAMPLEBUFFERS, (self.multisample_samples > 0).into())?;
+		set_attribute(SDL_GLattr::SDL_GL_MULTISAMPLESAMPLES, self.multisample_samples.into())?;
+		set_attribute(SDL_GLattr::SDL_GL_FRAMEBUFFER_SRGB_CAPABLE, self.srgb.into())?;
+		set_attribute(SDL_GLattr::SDL_GL_DOUBLEBUFFER, self.double_buffer.into())?;
+
+		Ok(())
+	}
+
+	/// Compares this configuration with the attributes of a context built from it.
+	///
+	/// Sizes and versions only mismatch when less than requested was obtained, as drivers are
+	/// free to give more.
+	pub fn mismatches(&self, obtained: &ContextAttributes) -> Vec<AttributeMismatch> {
+		let mut mismatches = Vec::new();
+		let mut check = |attribute, matches: bool, requested: String, obtained: String| {
+			if !matches {
+				mismatches.push(AttributeMismatch { attribute, requested, obtained });
+			}
+		};
+
+		let Version(api, major, minor) = obtained.version;
+		let requested_api = match self.profile {
+			Some(GLProfile::GLES) => Api::GlEs,
+			_ => Api::Gl,
+		};
+		if let Some((requested_major, requested_minor)) = self.version {
+			check(
+				"version",
+				api == requested_api && (major, minor) >= (requested_major, requested_minor),
+				format!("{} {}.{}", api_name(requested_api), requested_major, requested_minor),
+				format!("{} {}.{}", api_name(api), major, minor),
+			);
+		}
+
+		if let Some(profile) = self.profile {
+			let matches = match (profile, obtained.profile) {
+				(GLProfile::Core, Some(Profile::Core)) => true,
+				// contexts older than 3.2 don't have a profile, and are compatibility ones
+				(GLProfile::Compatibility, Some(Profile::Compatibility)) | (GLProfile::Compatibility, None) => {
+					api == Api::Gl
+				}
+				(GLProfile::GLES, _) => api == Api::GlEs,
+				_ => false,
+			};
+			check("profile", matches, format!("{:?}", profile), profile_name(api, obtained.profile));
+		}
+
+		if self.debug {
+			check("debug", obtained.debug, "true".into(), obtained.debug.to_string());
+		}
+		if self.forward_compatible {
+			check(
+				"forward compatible",
+				obtained.forward_compatible,
+				"true".into(),
+				obtained.forward_compatible.to_string(),
+			);
+		}
+		if self.robust {
+			check("robust", obtained.robust, "true".into(), obtained.robust.to_string());
+		}
+
+		check(
+			"depth bits",
+			obtained.depth_bits >= self.depth_bits,
+			self.depth_bits.to_string(),
+			obtained.depth_bits.to_string(),
+		);
+		check(
+			"stencil bits",
+			obtained.stencil_bits >= self.stencil_bits,
+			self.stencil_bits.to_string(),
+			obtained.stencil_bits.to_string(),
+		);
+		check(
+			"multisample samples",
+			obtained.multisample_samples >= self.multisample_samples,
+			self.multisample_samples.to_string(),
+			obtained.multisample_samples.to_string(),
+		);
+		if self.srgb {
+			check("sRGB", obtained.srgb, "true".into(), obtained.srgb.to_string());
+		}
+		check(
+			"double buffer",
+			obtained.double_buffer == self.double_buffer,
+			self.double_buffer.to_string(),
+			obtained.double_buffer.to_string(),
+		);
+
+		mismatches
+	}
+}
+
+fn api_name(api: Api) -> &'static str {
+	match api {
+		Api::Gl => "OpenGL",
+		Api::GlEs => "OpenGL ES",
+	}
+}
+
+fn profile_name(api: Api, profile: Option<Profile>) -> String {
+	match (api, profile) {
+		(Api::GlEs, _) => "GLES".into(),
+		(Api::Gl, Some(profile)) => format!("{:?}", profile),
+		(Api::Gl, None) => "none".into(),
+	}
+}
+
+/// Compares the context of a facade with one meant to replace it in `rebuild_glium`.
+///
+/// glium keeps the version and capabilities it detected when the facade was built, so the new
+/// context must have the same version, profile and forward compatibility. It must also have a
+/// depth and a stencil buffer if and only if the old one did, as glium checks for them before
+/// drawing.
+pub(crate) fn rebuild_mismatches(current: &Context, new: &Context) -> Vec<AttributeMismatch> {
+	let mut mismatches = Vec::new();
+	let mut check = |attribute, requested: String, obtained: String| {
+		if requested != obtained {
+			mismatches.push(AttributeMismatch { attribute, requested, obtained });
+		}
+	};
+
+	let version = |context: &Context| {
+		let Version(api, major, minor) = *context.get_opengl_version();
+		format!("{} {}.{}", api_name(api), major, minor)
+	};
+	check("version", version(current), version(new));
+
+	let (current_api, new_api) = (current.get_opengl_version().0, new.get_opengl_version().0);
+	let (current, new) = (current.get_capabilities(), new.get_capabilities());
+	check("profile", profile_name(current_api, current.profile), profile_name(new_api, new.profile));
+	check("forward compatible", current.forward_compatible.to_string(), new.forward_compatible.to_string());
+	check("depth buffer", current.depth_bits.is_some().to_string(), new.depth_bits.is_some().to_string());
+	check("stencil buffer", current.stencil_bits.is_some().to_string(), new.stencil_bits.is_some().to_string());
+
+	mismatches
+}
+
+/// The attributes of an OpenGL context, as obtained from the driver.
+///
+/// These can differ from what was requested: SDL silently falls back to fewer samples,
+/// smaller buffers or an older version when the requested ones aren't available.
+#[derive(Clone, Debug)]
+pub struct ContextAttributes {
+	/// The version of the context.
+	pub version: Version,
+
+	/// The profile of the context, for OpenGL 3.2 and above.
+	pub profile: Option<Profile>,
+
+	/// Whether the context is a debug context.
+	pub debug: bool,
+
+	/// Whether the context is forward-compatible.
+	pub forward_compatible: bool,
+
+	/// Whether out-of-bounds accesses are guaranteed not to crash.
+	pub robust: bool,
+
+	/// The number of bits in the depth buffer.
+	pub depth_bits: u8,
+
+	/// The number of bits in the stencil buffer.
+	pub stencil_bits: u8,
+
+	/// The number of samples per pixel, or 0 if multisampling is disabled.
+	pub multisample_samples: u8,
+
+	/// Whether the framebuffer is sRGB-capable.
+	pub srgb: bool,
+
+	/// Whether the framebuffer is double buffered.
+	pub double_buffer: bool,
+
+	/// The `GL_VENDOR` string.
+	pub vendor: String,
+
+	/// The `GL_RENDERER` string.
+	pub renderer: String,
+
+	/// The `GL_VERSION` string.
+	pub version_string: String,
+}
+
+/// An attribute for which less than requested was obtained.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeMismatch {
+	/// The name of the attribute.
+	pub attribute: &'static str,
+
+	/// The requested value.
+	pub requested: String,
+
+	/// The obtained value.
+	pub obtained: String,
+}
+
+impl fmt::Display for AttributeMismatch {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		write!(formatter, "{}: requested {}, obtained {}", self.attribute, self.requested, self.obtained)
+	}
+}
+
+/// The framebuffer attributes of the current context, which glium doesn't report.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct FramebufferAttributes {
+	pub depth_bits: u8,
+	pub stencil_bits: u8,
+	pub multisample_samples: u8,
+	pub double_buffer: bool,
+}
+
+impl FramebufferAttributes {
+	/// Queries the attributes of the current context's default framebuffer.
+	///
+	/// Must be called while the default framebuffer is bound, as SDL queries whichever
+	/// framebuffer is.
+	pub(crate) fn query() -> FramebufferAttributes {
+		let samples = match get_attribute(SDL_GLattr::SDL_GL_MULTISAMPLEBUFFERS) {
+			Ok(0) => Ok(0),
+			_ => get_attribute(SDL_GLattr::SDL_GL_MULTISAMPLESAMPLES),
+		};
+
+		FramebufferAttributes {
+			depth_bits: get_attribute(SDL_GLattr::SDL_GL_DEPTH_SIZE).unwrap_or(0) as u8,
+			stencil_bits: get_attribute(SDL_GLattr::SDL_GL_STENCIL_SIZE).unwrap_or(0) as u8,
+			multisample_samples: samples.unwrap_or(0) as u8,
+			double_buffer: get_attribute(SDL_GLattr::SDL_GL_DOUBLEBUFFER).unwrap_or(1) != 0,
+		}
+	}
+}
+
+const CONTEXT_DEBUG_FLAG: c_int = 0x0001;
+const CONTEXT_FORWARD_COMPATIBLE_FLAG: c_int = 0x0002;
+const CONTEXT_ROBUST_ACCESS_FLAG: c_int = 0x0004;
+const CONTEXT_RESET_ISOLATION_FLAG: c_int = 0x0008;
+
+/// The attributes that SDL reports as they were requested.
+///
+/// SDL answers queries for the framebuffer attributes (depth bits, samples, ...) with the values
+/// of the current context instead, and can't be queried for the reset notification strategy.
+const RESTORABLE_ATTRIBUTES: &[SDL_GLattr] = &[
+	SDL_GLattr::SDL_GL_CONTEXT_MAJOR_VERSION,
+	SDL_GLattr::SDL_GL_CONTEXT_MINOR_VERSION,
+	SDL_GLattr::SDL_GL_CONTEXT_PROFILE_MASK,
+	SDL_GLattr::SDL_GL_CONTEXT_FLAGS,
+	SDL_GLattr::SDL_GL_SHARE_WITH_CURRENT_CONTEXT,
+	SDL_GLattr::SDL_GL_FRAMEBUFFER_SRGB_CAPABLE,
+	SDL_GLattr::SDL_GL_CONTEXT_NO_ERROR,
+	SDL_GLattr::SDL_GL_ACCELERATED_VISUAL,
+	SDL_GLattr::SDL_GL_RETAINED_BACKING,
+];
+
+/// SDL's defaults for the framebuffer attributes of `GlConfig`, which can't be restored.
+const FRAMEBUFFER_DEFAULTS: &[(SDL_GLattr, c_int)] = &[
+	(SDL_GLattr::SDL_GL_DEPTH_SIZE, 16),
+	(SDL_GLattr::SDL_GL_STENCIL_SIZE, 0),
+	(SDL_GLattr::SDL_GL_MULTISAMPLEBUFFERS, 0),
+	(SDL_GLattr::SDL_GL_MULTISAMPLESAMPLES, 0),
+	(SDL_GLattr::SDL_GL_DOUBLEBUFFER, 1),
+];
+
+/// Returns the names and values of the attributes that the next context will be requested with.
+///
+/// Only the attributes that SDL can report back are included.
+pub(crate) fn requested_attributes() -> Vec<(String, i32)> {
+	RESTORABLE_ATTRIBUTES
+		.iter()
+		.filter_map(|&attr| get_attribute(attr).ok().map(|value| (attribute_name(attr).into(), value)))
+		.collect()
+}
+
+fn attribute_name(attr: SDL_GLattr) -> &'static str {
+	match attr {
+		SDL_GLattr::SDL_GL_CONTEXT_MAJOR_VERSION => "SDL_GL_CONTEXT_MAJOR_VERSION",
+		SDL_GLattr::SDL_GL_CONTEXT_MINOR_VERSION => "SDL_GL_CONTEXT_MINOR_VERSION",
+		SDL_GLattr::SDL_GL_CONTEXT_PROFILE_MASK => "SDL_GL_CONTEXT_PROFILE_MASK",
+		SDL_GLattr::SDL_GL_CONTEXT_FLAGS => "SDL_GL_CONTEXT_FLAGS",
+		SDL_GLattr::SDL_GL_SHARE_WITH_CURRENT_CONTEXT => "SDL_GL_SHARE_WITH_CURRENT_CONTEXT",
+		SDL_GLattr::SDL_GL_FRAMEBUFFER_SRGB_CAPABLE => "SDL_GL_FRAMEBUFFER_SRGB_CAPABLE",
+		SDL_GLattr::SDL_GL_CONTEXT_NO_ERROR => "SDL_GL_CONTEXT_NO_ERROR",
+		SDL_GLattr::SDL_GL_ACCELERATED_VISUAL => "SDL_GL_ACCELERATED_VISUAL",
+		SDL_GLattr::SDL_GL_RETAINED_BACKING => "SDL_GL_RETAINED_BACKING",
+		_ => "unknown",
+	}
+}
+
+/// A snapshot of SDL's OpenGL attributes, to be put back once a window has been built.
+pub(crate) struct SavedAttributes {
+	values: Vec<(SDL_GLattr, c_int)>,
+}
+
+impl SavedAttributes {
+	pub(crate) fn save() -> Result<SavedAttributes, String> {
+		let values = RESTORABLE_ATTRIBUTES
+			.iter()
+			.map(|&attr| get_attribute(attr).map(|value| (attr, value)))
+			.collect::<Result<_, _>>()?;
+
+		Ok(SavedAttributes { values })
+	}
+
+	/// Puts the saved attributes back, and the framebuffer attributes to SDL's defaults.
+	///
+	/// The reset notification strategy can't be saved. It's put back to the one that
+	/// `request_robust_context` sets if the saved context flags ask for robust access, and to
+	/// SDL's default otherwise.
+	pub(crate) fn restore(self) -> Result<(), String> {
+		let mut robust = false;
+		for (attr, value) in self.values {
+			set_attribute(attr, value)?;
+			if attr == SDL_GLattr::SDL_GL_CONTEXT_FLAGS {
+				robust = value & CONTEXT_ROBUST_ACCESS_FLAG != 0;
+			}
+		}
+
+		for &(attr, value) in FRAMEBUFFER_DEFAULTS {
+			set_attribute(attr, value)?;
+		}
+
+		set_reset_notification(robust)
+	}
+}
+
+/// Asks for contexts that are lost when the GPU is reset, so that resets can be detected, or
+/// for SDL's default of contexts that don't report resets.
+pub(crate) fn set_reset_notification(lose_context_on_reset: bool) -> Result<(), String> {
+	// `SDL_GL_CONTEXT_RESET_LOSE_CONTEXT` or `SDL_GL_CONTEXT_RESET_NO_NOTIFICATION`
+	set_attribute(SDL_GLattr::SDL_GL_CONTEXT_RESET_NOTIFICATION, lose_context_on_reset.into())
+}
+
+fn set_attribute(attr: SDL_GLattr, value: c_int) -> Result<(), String> {
+	if unsafe { sys::SDL_GL_SetAttribute(attr, value) } == 0 {
+		Ok(())
+	} else {
+		Err(sdl2::get_error())
+	}
+}
+
+fn get_attribute(attr: SDL_GLattr) -> Result<c_int, String> {
+	let mut value = 0;
+	if unsafe { sys::SDL_GL_GetAttribute(attr, &mut value) } == 0 {
+		Ok(value)
+	} else {
+		Err(sdl2::get_error())
+	}
+}
@@ -0,0 +1,149 @@
+//! Color cursors made from glium textures, for `SDL2Facade::set_cursor_from_texture`.
+
+use std::time::Duration;
+
+use glium::texture::{RawImage2d, Texture2d};
+use sdl2::mouse::Cursor;
+
+use surface;
+use GliumSdl2Error;
+
+/// Creates an SDL cursor showing a texture, whose hotspot is in pixels from its top left.
+fn cursor_from_texture(texture: &Texture2d, hotspot: (i32, i32)) -> Result<Cursor, GliumSdl2Error> {
+	let image: RawImage2d<u8> = texture.read();
+	let surface = surface::surface_from_raw_image(&image)?;
+	Cursor::from_surface(surface, hotspot.0, hotspot.1).map_err(GliumSdl2Error::CursorError)
+}
+
+/// The frames of an animation, each shown for its duration in turn.
+struct Animation {
+	durations: Vec<Duration>,
+	current: usize,
+
+	// the time spent on the current frame
+	elapsed: Duration,
+}
+
+impl Animation {
+	fn new(durations: Vec<Duration>) -> Animation {
+		Animation { durations, current: 0, elapsed: Duration::from_secs(0) }
+	}
+
+	/// Advances the animation, and returns whether it moved to another frame.
+	fn advance(&mut self, elapsed: Duration) -> bool {
+		let cycle: Duration = self.durations.iter().sum();
+		if cycle == Duration::from_secs(0) {
+			return false;
+		}
+
+		// whole cycles come back to the same frame
+		let elapsed = (self.elapsed + elapsed).as_nanos() % cycle.as_nanos();
+		self.elapsed = Duration::from_nanos(elapsed as u64);
+
+		let previous = self.current;
+		while self.elapsed >= self.durations[self.current] {
+			self.elapsed -= self.durations[self.current];
+			self.current = (self.current + 1) % self.durations.len();
+		}
+
+		self.current != previous
+	}
+}
+
+/// The cursor set by `SDL2Facade::set_cursor_from_texture` or `set_animated_cursor`.
+///
+/// SDL goes back to the default cursor when the cursor it shows is destroyed, so this must be
+/// kept alive for as long as the cursor is shown. Animated cursors move to their next frame in
+/// `update`.
+pub struct CursorHandle {
+	cursors: Vec<Cursor>,
+	animation: Animation,
+}
+
+impl CursorHandle {
+	pub(crate) fn new(
+		frames: &[(&Texture2d, Duration)],
+		hotspot: (i32, i32),
+	) -> Result<CursorHandle, GliumSdl2Error> {
+		assert!(!frames.is_empty(), "a cursor needs at least one frame");
+
+		let cursors = frames
+			.iter()
+			.map(|&(texture, _)| cursor_from_texture(texture, hotspot))
+			.collect::<Result<Vec<_>, GliumSdl2Error>>()?;
+		let animation = Animation::new(frames.iter().map(|&(_, duration)| duration).collect());
+
+		let handle = CursorHandle { cursors, animation };
+		handle.set();
+		Ok(handle)
+	}
+
+	/// Shows the cursor again, after another cursor was set.
+	pub fn set(&self) {
+		self.cursors[self.animation.current].set();
+	}
+
+	/// The number of frames of the cursor, 1 if it isn't animated.
+	pub fn frames(&self) -> usize {
+		self.cursors.len()
+	}
+
+	/// The index of the frame being shown.
+	pub fn current_frame(&self) -> usize {
+		self.animation.current
+	}
+
+	/// Advances the animation by `elapsed`, such as the time since the last call or the
+	/// timestep of a `GameLoop`, and shows the frame reached, if it changed.
+	pub fn update(&mut self, elapsed: Duration) {
+		if self.animation.advance(elapsed) {
+			self.set();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Animation;
+	use std::time::Duration;
+
+	fn millis(millis: u64) -> Duration {
+		Duration::from_millis(millis)
+	}
+
+	#[test]
+	fn frames_follow_their_durations() {
+		let mut animation = Animation::new(vec![millis(100), millis(50), millis(100)]);
+
+		assert!(!animation.advance(millis(99)));
+		assert_eq!(animation.current, 0);
+		assert!(animation.advance(millis(1)));
+		assert_eq!(animation.current, 1);
+		assert!(animation.advance(millis(60)));
+		assert_eq!((animation.current, animation.elapsed), (2, millis(10)));
+	}
+
+	#[test]
+	fn long_pauses_wrap_around() {
+		let mut animation = Animation::new(vec![millis(100), millis(100), millis(100)]);
+
+		assert!(animation.advance(millis(150)));
+		assert!(animation.advance(millis(200)));
+		assert_eq!((animation.current, animation.elapsed), (0, millis(50)));
+
+		// 10 whole cycles, and 100 ms more
+		assert!(animation.advance(millis(3100)));
+		assert_eq!((animation.current, animation.elapsed), (1, millis(50)));
+	}
+
+	#[test]
+	fn still_cursors_never_change() {
+		let mut animation = Animation::new(vec![millis(0)]);
+		assert!(!animation.advance(millis(1000)));
+
+		// frames without a duration are skipped
+		let mut animation = Animation::new(vec![millis(100), millis(0), millis(100)]);
+		assert!(animation.advance(millis(100)));
+		assert_eq!(animation.current, 2);
+	}
+}
@@ -0,0 +1,186 @@
+//! A report on the system that a facade is built on, for bug and crash reports.
+
+use std::fmt;
+
+use glium::{Api, Version};
+use sdl2::VideoSubsystem;
+
+use config;
+use gl;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// What is known about the system when building a facade, or when it fails to build.
+///
+/// The `Display` implementation gives a human-readable report. With the `serde` feature,
+/// this can also be serialized, for example into a crash report.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Diagnostics {
+	/// The name of SDL's video driver, such as "x11", "wayland" or "windows".
+	pub video_driver: String,
+
+	/// The version of the SDL library loaded at runtime.
+	pub sdl_version: String,
+
+	/// The OpenGL attributes that contexts are requested with, as names of `SDL_GLattr` with
+	/// their values.
+	///
+	/// SDL only reports the requested values of the context attributes (version, profile,
+	/// flags...). The framebuffer ones are those of `ContextAttributes`.
+	pub requested_attributes: Vec<(String, i32)>,
+
+	/// The description of the context, if one could be created.
+	pub context: Option<ContextDescription>,
+
+	/// The extensions that glium needs but that the context doesn't have.
+	pub missing_extensions: Vec<String>,
+
+	/// The error that happened while creating a context, if any.
+	pub error: Option<String>,
+}
+
+/// The strings that an OpenGL context describes itself with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ContextDescription {
+	pub vendor: String,
+	pub renderer: String,
+	pub version: String,
+	pub glsl_version: String,
+}
+
+impl Diagnostics {
+	/// Collects what doesn't need a context.
+	fn without_context(video: &VideoSubsystem) -> Diagnostics {
+		let version = sdl2::version::version();
+
+		Diagnostics {
+			video_driver: video.current_video_driver().into(),
+			sdl_version: version.to_string(),
+			requested_attributes: config::requested_attributes(),
+			context: None,
+			missing_extensions: Vec::new(),
+			error: None,
+		}
+	}
+
+	/// Collects everything, describing the current context.
+	pub(crate) fn with_current_context(video: &VideoSubsystem) -> Diagnostics {
+		let get_string = |name| gl::get_string(video, name).unwrap_or_default();
+		let context = ContextDescription {
+			vendor: get_string(gl::VENDOR),
+			renderer: get_string(gl::RENDERER),
+			version: get_string(gl::VERSION),
+			glsl_version: get_string(gl::SHADING_LANGUAGE_VERSION),
+		};
+
+		let missing_extensions = match parse_version(&context.version) {
+			Some(version) => missing_extensions(&version, |extension| video.gl_extension_supported(extension)),
+			None => Vec::new(),
+		};
+
+		Diagnostics { context: Some(context), missing_extensions, ..Diagnostics::without_context(video) }
+	}
+}
+
+impl fmt::Display for Diagnostics {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(formatter, "SDL version: {}", self.sdl_version)?;
+		writeln!(formatter, "video driver: {}", self.video_driver)?;
+
+		writeln!(formatter, "requested attributes:")?;
+		for &(ref name, value) in &self.requested_attributes {
+			writeln!(formatter, "    {} = {}", name, value)?;
+		}
+
+		match self.context {
+			Some(ref context) => {
+				writeln!(formatter, "OpenGL vendor: {}", context.vendor)?;
+				writeln!(formatter, "OpenGL renderer: {}", context.renderer)?;
+				writeln!(formatter, "OpenGL version: {}", context.version)?;
+				writeln!(formatter, "GLSL version: {}", context.glsl_version)?;
+			}
+			None => writeln!(formatter, "no OpenGL context could be created")?,
+		}
+
+		if !self.missing_extensions.is_empty() {
+			writeln!(formatter, "missing extensions: {}", self.missing_extensions.join(", "))?;
+		}
+
+		if let Some(ref error) = self.error {
+			writeln!(formatter, "error: {}", error)?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Collects a diagnostic report on the OpenGL support of the video subsystem.
+///
+/// This creates a hidden window with a context, using the OpenGL attributes that are currently
+/// set, to find out what the driver provides. It works whether or not building a facade would
+/// succeed, so it can be called after `build_glium` fails to tell why. The context of an
+/// existing facade is described by `SDL2Facade::diagnostics` instead.
+///
+/// Leaves no context current.
+pub fn facade_diagnostics(video: &VideoSubsystem) -> Diagnostics {
+	let window = match video.window("glium_sdl2 diagnostics", 1, 1).hidden().opengl().build() {
+		Ok(window) => window,
+		Err(err) => {
+			return Diagnostics { error: Some(err.to_string()), ..Diagnostics::without_context(video) };
+		}
+	};
+
+	let _context = match window.gl_create_context() {
+		Ok(context) => context,
+		Err(err) => return Diagnostics { error: Some(err), ..Diagnostics::without_context(video) },
+	};
+
+	Diagnostics::with_current_context(video)
+}
+
+/// Parses the version string of a context, such as "4.6 (Core Profile) Mesa 23.0" or
+/// "OpenGL ES 3.2 Mesa 23.0".
+fn parse_version(version: &str) -> Option<Version> {
+	let (api, version) = match version.find("OpenGL ES") {
+		Some(start) => {
+			let rest = &version[start + "OpenGL ES".len()..];
+			// OpenGL ES 1.x says "OpenGL ES-CM 1.1"
+			let rest = rest.trim_start_matches(|c: char| c != ' ');
+			(Api::GlEs, rest.trim_start())
+		}
+		None => (Api::Gl, version),
+	};
+
+	let number = version.split(' ').next()?;
+	let mut parts = number.split('.');
+	let major = parts.next()?.parse().ok()?;
+	let minor = parts.next()?.parse().ok()?;
+
+	Some(Version(api, major, minor))
+}
+
+/// Lists the extensions that glium's compatibility check needs and that aren't supported.
+fn missing_extensions<F: Fn(&str) -> bool>(version: &Version, supported: F) -> Vec<String> {
+	// Versions of different APIs don't compare, and ES 2.0 has everything glium needs.
+	let at_least = |min: Version| *version >= min;
+	let older_than = |gl_version: Version| !at_least(gl_version) && !at_least(Version(Api::GlEs, 2, 0));
+
+	let mut required = Vec::new();
+	if older_than(Version(Api::Gl, 1, 5)) {
+		required.extend(&["GL_ARB_vertex_buffer_object", "GL_ARB_map_buffer_range"]);
+	}
+	if older_than(Version(Api::Gl, 2, 0)) {
+		required.extend(&["GL_ARB_shader_objects", "GL_ARB_vertex_shader", "GL_ARB_fragment_shader"]);
+	}
+	if older_than(Version(Api::Gl, 3, 0)) {
+		if !supported("GL_EXT_framebuffer_object") {
+			required.push("GL_ARB_framebuffer_object");
+		}
+		required.push("GL_EXT_framebuffer_blit");
+	}
+
+	required.into_iter().filter(|extension| !supported(extension)).map(String::from).collect()
+}
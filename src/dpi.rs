@@ -0,0 +1,101 @@
+//! Conversions between window coordinates and framebuffer pixels.
+
+use sdl2::rect::Rect;
+
+/// Scales a coordinate from a length in one unit to the same length in another.
+fn scale(value: i32, from: u32, to: u32) -> i32 {
+	if from == 0 || from == to {
+		return value;
+	}
+
+	(f64::from(value) * f64::from(to) / f64::from(from)).round() as i32
+}
+
+/// Scales a point from a space of size `from` to a space of size `to`.
+pub(crate) fn scale_point(point: (i32, i32), from: (u32, u32), to: (u32, u32)) -> (i32, i32) {
+	(scale(point.0, from.0, to.0), scale(point.1, from.1, to.1))
+}
+
+/// Scales a rectangle from a space of size `from` to a space of size `to`.
+///
+/// The corners are scaled rather than the size, so that adjacent rectangles stay adjacent.
+pub(crate) fn scale_rect(rect: Rect, from: (u32, u32), to: (u32, u32)) -> Rect {
+	let (left, top) = scale_point((rect.left(), rect.top()), from, to);
+	let (right, bottom) = scale_point((rect.right(), rect.bottom()), from, to);
+
+	// SDL rectangles are at least 1 pixel wide and high
+	Rect::new(left, top, (right - left).max(1) as u32, (bottom - top).max(1) as u32)
+}
+
+/// The ratio of drawable pixels to window units along the horizontal axis.
+pub(crate) fn scale_factor(window_size: (u32, u32), drawable_size: (u32, u32)) -> f32 {
+	if window_size.0 == 0 {
+		return 1.0;
+	}
+
+	drawable_size.0 as f32 / window_size.0 as f32
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{scale_factor, scale_point, scale_rect};
+	use sdl2::rect::Rect;
+
+	// a HiDPI window, whose drawable is twice its size
+	const WINDOW: (u32, u32) = (400, 300);
+	const DRAWABLE: (u32, u32) = (800, 600);
+
+	#[test]
+	fn points_are_scaled_both_ways() {
+		assert_eq!(scale_point((10, 20), WINDOW, DRAWABLE), (20, 40));
+		assert_eq!(scale_point((20, 40), DRAWABLE, WINDOW), (10, 20));
+		assert_eq!(scale_point((10, 20), WINDOW, WINDOW), (10, 20));
+	}
+
+	#[test]
+	fn halves_are_rounded_away_from_zero() {
+		assert_eq!(scale_point((3, 5), DRAWABLE, WINDOW), (2, 3));
+		assert_eq!(scale_point((-3, -5), DRAWABLE, WINDOW), (-2, -3));
+	}
+
+	#[test]
+	fn negative_points_are_scaled() {
+		// points left of or above the window, as reported while dragging out of it
+		assert_eq!(scale_point((-10, -1), WINDOW, DRAWABLE), (-20, -2));
+		assert_eq!(scale_point((-20, -2), DRAWABLE, WINDOW), (-10, -1));
+	}
+
+	#[test]
+	fn empty_spaces_leave_points_unchanged() {
+		assert_eq!(scale_point((7, -7), (0, 0), DRAWABLE), (7, -7));
+	}
+
+	#[test]
+	fn adjacent_rects_stay_adjacent() {
+		// odd widths in drawable pixels, which fall between window units
+		let rects: Vec<Rect> = (0..10).map(|i| Rect::new(i * 3 - 15, 1, 3, 3)).collect();
+		let scaled: Vec<Rect> = rects.iter().map(|&rect| scale_rect(rect, DRAWABLE, WINDOW)).collect();
+
+		for pair in scaled.windows(2) {
+			assert_eq!(pair[0].right(), pair[1].left());
+		}
+		assert_eq!(scaled[0].left(), -8);
+		assert_eq!(scaled[9].right(), 8);
+	}
+
+	#[test]
+	fn rects_are_at_least_a_pixel_wide() {
+		let rect = scale_rect(Rect::new(1, 1, 1, 1), DRAWABLE, WINDOW);
+		assert_eq!((rect.width(), rect.height()), (1, 1));
+
+		let rect = scale_rect(Rect::new(-5, 3, 10, 2), WINDOW, DRAWABLE);
+		assert_eq!(rect, Rect::new(-10, 6, 20, 4));
+	}
+
+	#[test]
+	fn scale_factor_is_the_ratio_of_the_widths() {
+		assert_eq!(scale_factor(WINDOW, DRAWABLE), 2.0);
+		assert_eq!(scale_factor(WINDOW, WINDOW), 1.0);
+		assert_eq!(scale_factor((0, 0), DRAWABLE), 1.0);
+	}
+}
@@ -0,0 +1,412 @@
+//! A fixed-timestep game loop driving a facade and an event pump.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use glium::{Frame, SwapBuffersError};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::EventPump;
+
+use SDL2Facade;
+
+/// A source of time for `GameLoop`.
+///
+/// The loop only reads time through this trait, so that tests can drive it with a fake clock.
+pub trait Clock {
+	/// Returns the time elapsed since an arbitrary, fixed origin.
+	fn now(&self) -> Duration;
+
+	/// Blocks the thread for the given duration.
+	fn sleep(&self, duration: Duration);
+}
+
+/// The clock of the operating system.
+#[derive(Copy, Clone, Debug)]
+pub struct SystemClock {
+	origin: Instant,
+}
+
+impl SystemClock {
+	pub fn new() -> SystemClock {
+		SystemClock { origin: Instant::now() }
+	}
+}
+
+impl Default for SystemClock {
+	fn default() -> SystemClock {
+		SystemClock::new()
+	}
+}
+
+impl Clock for SystemClock {
+	fn now(&self) -> Duration {
+		self.origin.elapsed()
+	}
+
+	fn sleep(&self, duration: Duration) {
+		thread::sleep(duration)
+	}
+}
+
+/// How `GameLoop` waits for the next update once a frame is drawn.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pacing {
+	/// Don't wait, and draw as many frames as possible. Use this when vsync paces the frames.
+	None,
+
+	/// Sleep until the next update is due. Cheap, but the OS may oversleep by a millisecond
+	/// or more.
+	Sleep,
+
+	/// Busy-wait until the next update is due. Precise, but keeps a CPU core busy.
+	Spin,
+
+	/// Sleep until the given margin before the next update, then busy-wait.
+	SleepThenSpin(Duration),
+}
+
+/// Whether `GameLoop::run` keeps going after an event.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LoopControl {
+	Continue,
+	Stop,
+}
+
+/// The callbacks of a game driven by `GameLoop::run`.
+pub trait Game {
+	/// Handles an event of the event pump. By default, the loop stops on `Event::Quit`.
+	fn event(&mut self, event: &Event) -> LoopControl {
+		match *event {
+			Event::Quit { .. } => LoopControl::Stop,
+			_ => LoopControl::Continue,
+		}
+	}
+
+	/// Advances the game state by one fixed timestep.
+	fn update(&mut self, timestep: Duration);
+
+	/// Draws the game state.
+	///
+	/// `alpha`, from 0 to 1, is how far the time of the frame is between the last update and
+	/// the next one, to interpolate positions with.
+	fn render(&mut self, frame: &mut Frame, alpha: f64);
+}
+
+/// What the loop has to do for one frame, as returned by `GameLoop::tick`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tick {
+	/// The number of fixed updates to run before drawing.
+	pub updates: u32,
+
+	/// The interpolation factor to draw with, from 0 to 1.
+	pub alpha: f64,
+
+	/// The time that was dropped because it would have taken more than the maximum number
+	/// of updates to catch up with.
+	pub dropped: Duration,
+}
+
+/// A loop that updates a game at a fixed rate and draws it as often as possible.
+///
+/// Time is accumulated between frames and consumed in fixed timesteps, so the game behaves the
+/// same at any frame rate. If the game falls behind, for example after a long stall, at most
+/// `max_updates_per_frame` updates are run per frame and the rest of the backlog is dropped,
+/// rather than spiralling into ever longer frames.
+pub struct GameLoop<C: Clock = SystemClock> {
+	clock: C,
+	timestep: Duration,
+	max_updates_per_frame: u32,
+	pacing: Pacing,
+	accumulator: Duration,
+	previous: Option<Duration>,
+}
+
+impl GameLoop<SystemClock> {
+	/// Creates a loop updating at the given rate, such as 60 updates per second.
+	///
+	/// # Panics
+	///
+	/// Panics if `updates_per_second` is 0.
+	pub fn new(updates_per_second: u32) -> GameLoop<SystemClock> {
+		GameLoop::with_clock(updates_per_second, SystemClock::new())
+	}
+}
+
+impl<C: Clock> GameLoop<C> {
+	/// Creates a loop updating at the given rate, that reads time from the given clock.
+	///
+	/// # Panics
+	///
+	/// Panics if `updates_per_second` is 0.
+	pub fn with_clock(updates_per_second: u32, clock: C) -> GameLoop<C> {
+		assert!(updates_per_second > 0, "the update rate must not be 0");
+
+		GameLoop {
+			clock,
+			timestep: Duration::from_secs(1) / updates_per_second,
+			max_updates_per_frame: 10,
+			pacing: Pacing::Sleep,
+			accumulator: Duration::from_secs(0),
+			previous: None,
+		}
+	}
+
+	/// Sets the maximum number of updates run before a frame. Defaults to 10.
+	pub fn set_max_updates_per_frame(&mut self, max_updates_per_frame: u32) {
+		self.max_updates_per_frame = max_updates_per_frame.max(1);
+	}
+
+	/// Sets how the loop waits between frames. Defaults to `Pacing::Sleep`.
+	pub fn set_pacing(&mut self, pacing: Pacing) {
+		self.pacing = pacing;
+	}
+
+	/// Returns the duration of a fixed update.
+	pub fn timestep(&self) -> Duration {
+		self.timestep
+	}
+
+	pub fn clock(&self) -> &C {
+		&self.clock
+	}
+
+	/// Forgets the time accumulated so far, so that the next tick starts afresh.
+	///
+	/// `run` does this when the window is restored after having been minimized.
+	pub fn reset(&mut self) {
+		self.accumulator = Duration::from_secs(0);
+		self.previous = None;
+	}
+
+	/// Accumulates the time elapsed since the last tick, and returns what to do for the frame.
+	///
+	/// `run` calls this once per frame. It's public to build other loops on top of it.
+	pub fn tick(&mut self) -> Tick {
+		let now = self.clock.now();
+		let elapsed = self.previous.map_or(Duration::from_secs(0), |previous| now - previous);
+		self.previous = Some(now);
+		self.accumulator += elapsed;
+
+		let mut updates = 0;
+		while self.accumulator >= self.timestep && updates < self.max_updates_per_frame {
+			self.accumulator -= self.timestep;
+			updates += 1;
+		}
+
+		// drop whole timesteps that couldn't be caught up with, but keep the fraction
+		let mut dropped = Duration::from_secs(0);
+		if self.accumulator >= self.timestep {
+			let remainder = self.accumulator.as_nanos() % self.timestep.as_nanos();
+			let remainder = Duration::from_nanos(remainder as u64);
+			dropped = self.accumulator - remainder;
+			self.accumulator = remainder;
+		}
+
+		Tick { updates, alpha: self.accumulator.as_secs_f64() / self.timestep.as_secs_f64(), dropped }
+	}
+
+	/// Waits until the next update is due, according to the pacing.
+	pub fn pace(&mut self) {
+		let previous = match self.previous {
+			Some(previous) => previous,
+			None => return,
+		};
+		let deadline = previous + (self.timestep - self.accumulator);
+
+		let spin_margin = match self.pacing {
+			Pacing::None => return,
+			Pacing::Sleep => Duration::from_secs(0),
+			Pacing::Spin => self.timestep,
+			Pacing::SleepThenSpin(margin) => margin,
+		};
+
+		let now = self.clock.now();
+		if let Some(remaining) = deadline.checked_sub(now) {
+			if let Some(sleep) = remaining.checked_sub(spin_margin) {
+				self.clock.sleep(sleep);
+			}
+		}
+
+		if spin_margin > Duration::from_secs(0) {
+			while self.clock.now() < deadline {
+				std::hint::spin_loop();
+			}
+		}
+	}
+
+	/// Runs the game until one of its events returns `LoopControl::Stop`, or a frame fails.
+	///
+	/// Each iteration polls the events, which go through `SDL2Facade::handle_event` and then
+	/// to the game, runs the due updates, draws a frame, and waits according to the pacing.
+	/// While the window is minimized, the loop blocks on the event pump instead of drawing.
+	pub fn run<G: Game>(
+		&mut self,
+		display: &SDL2Facade,
+		event_pump: &mut EventPump,
+		game: &mut G,
+	) -> Result<(), SwapBuffersError> {
+		let window_id = display.window().id();
+		let mut minimized = display.window().is_minimized();
+		self.reset();
+
+		loop {
+			if minimized {
+				let event = event_pump.wait_event();
+				if handle_event(display, window_id, &mut minimized, game, &event) == LoopControl::Stop {
+					return Ok(());
+				}
+				if !minimized {
+					// the time spent minimized isn't made up for
+					self.reset();
+				}
+				continue;
+			}
+
+			for event in event_pump.poll_iter() {
+				if handle_event(display, window_id, &mut minimized, game, &event) == LoopControl::Stop {
+					return Ok(());
+				}
+			}
+
+			let tick = self.tick();
+			for _ in 0..tick.updates {
+				game.update(self.timestep);
+			}
+
+			let mut frame = display.draw();
+			game.render(&mut frame, tick.alpha);
+			frame.finish()?;
+
+			self.pace();
+		}
+	}
+}
+
+fn handle_event<G: Game>(
+	display: &SDL2Facade,
+	window_id: u32,
+	minimized: &mut bool,
+	game: &mut G,
+	event: &Event,
+) -> LoopControl {
+	display.handle_event(event);
+
+	if let Event::Window { window_id: id, ref win_event, .. } = *event {
+		if id == window_id {
+			match *win_event {
+				WindowEvent::Minimized | WindowEvent::Hidden => *minimized = true,
+				WindowEvent::Restored | WindowEvent::Maximized | WindowEvent::Shown => *minimized = false,
+				_ => {}
+			}
+		}
+	}
+
+	game.event(event)
+}
+
+// The loop is driven by a fake clock, which only moves when told to, when slept on, and by
+// `step` every time it's read.
+#[cfg(test)]
+mod tests {
+	use super::{Clock, GameLoop, Pacing};
+	use std::cell::Cell;
+	use std::time::Duration;
+
+	#[derive(Default)]
+	struct FakeClock {
+		now: Cell<Duration>,
+		step: Cell<Duration>,
+		slept: Cell<Duration>,
+	}
+
+	impl FakeClock {
+		fn advance(&self, duration: Duration) {
+			self.now.set(self.now.get() + duration);
+		}
+	}
+
+	impl Clock for FakeClock {
+		fn now(&self) -> Duration {
+			self.advance(self.step.get());
+			self.now.get()
+		}
+
+		fn sleep(&self, duration: Duration) {
+			self.advance(duration);
+			self.slept.set(self.slept.get() + duration);
+		}
+	}
+
+	fn ms(ms: u64) -> Duration {
+		Duration::from_millis(ms)
+	}
+
+	#[test]
+	fn runs_one_update_per_elapsed_timestep() {
+		let mut game_loop = GameLoop::with_clock(100, FakeClock::default());
+		assert_eq!(game_loop.tick().updates, 0);
+
+		game_loop.clock().advance(ms(35));
+		let tick = game_loop.tick();
+		assert_eq!(tick.updates, 3);
+		assert!((tick.alpha - 0.5).abs() < 1e-9);
+
+		game_loop.clock().advance(ms(5));
+		let tick = game_loop.tick();
+		assert_eq!(tick.updates, 1);
+		assert_eq!(tick.alpha, 0.0);
+	}
+
+	#[test]
+	fn drops_what_exceeds_the_frame_skip_limit() {
+		let mut game_loop = GameLoop::with_clock(100, FakeClock::default());
+		game_loop.set_max_updates_per_frame(4);
+		game_loop.tick();
+
+		game_loop.clock().advance(ms(1005));
+		let tick = game_loop.tick();
+		assert_eq!(tick.updates, 4);
+		assert_eq!(tick.dropped, ms(960));
+		assert!((tick.alpha - 0.5).abs() < 1e-9);
+
+		game_loop.clock().advance(ms(5));
+		assert_eq!(game_loop.tick().updates, 1);
+	}
+
+	#[test]
+	fn sleeps_until_the_next_update() {
+		let mut game_loop = GameLoop::with_clock(100, FakeClock::default());
+		game_loop.tick();
+		game_loop.clock().advance(ms(3));
+		game_loop.pace();
+		assert_eq!(game_loop.clock().slept.get(), ms(7));
+		assert_eq!(game_loop.tick().updates, 1);
+
+		game_loop.set_pacing(Pacing::None);
+		game_loop.pace();
+		assert_eq!(game_loop.clock().slept.get(), ms(7));
+	}
+
+	#[test]
+	fn spins_for_the_end_of_the_wait() {
+		let mut game_loop = GameLoop::with_clock(100, FakeClock::default());
+		game_loop.set_pacing(Pacing::SleepThenSpin(ms(2)));
+		game_loop.tick();
+		game_loop.clock().advance(ms(3));
+
+		// reading the time takes 1ms: 4ms have passed when deciding how long to sleep
+		game_loop.clock().step.set(ms(1));
+		game_loop.pace();
+		assert_eq!(game_loop.clock().slept.get(), ms(4));
+		assert_eq!(game_loop.clock().now.get(), ms(10));
+	}
+
+	#[test]
+	fn reset_forgets_the_time_spent_minimized() {
+		let mut game_loop = GameLoop::with_clock(100, FakeClock::default());
+		game_loop.tick();
+		game_loop.clock().advance(ms(60_000));
+		game_loop.reset();
+		assert_eq!(game_loop.tick().updates, 0);
+	}
+}
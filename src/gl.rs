@@ -0,0 +1,219 @@
+//! The few raw OpenGL entry points that glium doesn't expose, loaded through SDL.
+
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+
+use sdl2::VideoSubsystem;
+
+pub type GLenum = c_uint;
+pub type GLint = c_int;
+pub type GLuint = c_uint;
+pub type GLsizei = c_int;
+pub type GLbitfield = c_uint;
+pub type GLsync = *const c_void;
+
+pub const NO_ERROR: GLenum = 0;
+pub const LOSE_CONTEXT_ON_RESET: GLenum = 0x8252;
+pub const RESET_NOTIFICATION_STRATEGY: GLenum = 0x8256;
+pub const VENDOR: GLenum = 0x1F00;
+pub const RENDERER: GLenum = 0x1F01;
+pub const VERSION: GLenum = 0x1F02;
+pub const SHADING_LANGUAGE_VERSION: GLenum = 0x8B8C;
+pub const TIME_ELAPSED: GLenum = 0x88BF;
+pub const QUERY_RESULT: GLenum = 0x8866;
+pub const QUERY_RESULT_AVAILABLE: GLenum = 0x8867;
+pub const GPU_DISJOINT: GLenum = 0x8FBB;
+pub const SYNC_GPU_COMMANDS_COMPLETE: GLenum = 0x9117;
+pub const SYNC_FLUSH_COMMANDS_BIT: GLbitfield = 0x1;
+pub const TIMEOUT_EXPIRED: GLenum = 0x911B;
+pub const FRONT_LEFT: GLenum = 0x0400;
+pub const FRONT_AND_BACK: GLenum = 0x0408;
+pub const RGBA8: GLenum = 0x8058;
+pub const DEPTH24_STENCIL8: GLenum = 0x88F0;
+pub const FRAMEBUFFER: GLenum = 0x8D40;
+pub const READ_FRAMEBUFFER: GLenum = 0x8CA8;
+pub const DRAW_FRAMEBUFFER: GLenum = 0x8CA9;
+pub const RENDERBUFFER: GLenum = 0x8D41;
+pub const RENDERBUFFER_BINDING: GLenum = 0x8CA7;
+pub const FRAMEBUFFER_COMPLETE: GLenum = 0x8CD5;
+pub const COLOR_ATTACHMENT0: GLenum = 0x8CE0;
+pub const DEPTH_ATTACHMENT: GLenum = 0x8D00;
+pub const STENCIL_ATTACHMENT: GLenum = 0x8D20;
+
+pub type GetErrorFn = extern "system" fn() -> GLenum;
+pub type GetGraphicsResetStatusFn = extern "system" fn() -> GLenum;
+pub type GetIntegervFn = extern "system" fn(GLenum, *mut GLint);
+pub type GetStringFn = extern "system" fn(GLenum) -> *const c_char;
+pub type GenQueriesFn = extern "system" fn(GLsizei, *mut GLuint);
+pub type BeginQueryFn = extern "system" fn(GLenum, GLuint);
+pub type EndQueryFn = extern "system" fn(GLenum);
+pub type GetQueryObjectivFn = extern "system" fn(GLuint, GLenum, *mut GLint);
+pub type GetQueryObjectui64vFn = extern "system" fn(GLuint, GLenum, *mut u64);
+pub type FenceSyncFn = extern "system" fn(GLenum, GLbitfield) -> GLsync;
+pub type ClientWaitSyncFn = extern "system" fn(GLsync, GLbitfield, u64) -> GLenum;
+pub type DeleteSyncFn = extern "system" fn(GLsync);
+pub type GenFramebuffersFn = extern "system" fn(GLsizei, *mut GLuint);
+pub type BindFramebufferFn = extern "system" fn(GLenum, GLuint);
+pub type DeleteFramebuffersFn = extern "system" fn(GLsizei, *const GLuint);
+pub type CheckFramebufferStatusFn = extern "system" fn(GLenum) -> GLenum;
+pub type GenRenderbuffersFn = extern "system" fn(GLsizei, *mut GLuint);
+pub type BindRenderbufferFn = extern "system" fn(GLenum, GLuint);
+pub type RenderbufferStorageFn = extern "system" fn(GLenum, GLenum, GLsizei, GLsizei);
+pub type FramebufferRenderbufferFn = extern "system" fn(GLenum, GLenum, GLenum, GLuint);
+pub type ReadBufferFn = extern "system" fn(GLenum);
+pub type DrawBufferFn = extern "system" fn(GLenum);
+#[rustfmt::skip]
+pub type BlitNamedFramebufferFn = extern "system" fn(
+	GLuint, GLuint, GLint, GLint, GLint, GLint, GLint, GLint, GLint, GLint, GLbitfield, GLenum,
+);
+
+/// Loads the first of `names` that the current context provides.
+///
+/// # Safety
+///
+/// `F` must be an `extern "system" fn` type matching the signature of every name in `names`.
+pub unsafe fn load<F: Copy>(video: &VideoSubsystem, names: &[&str]) -> Option<F> {
+	debug_assert_eq!(mem::size_of::<F>(), mem::size_of::<*const ()>());
+
+	names
+		.iter()
+		.map(|name| video.gl_get_proc_address(name))
+		.find(|ptr| !ptr.is_null())
+		.map(|ptr| mem::transmute_copy(&ptr))
+}
+
+/// Returns `glGetGraphicsResetStatus` if the current context reports resets to the application.
+///
+/// A context only does so when it was created with the "lose context on reset" notification
+/// strategy, which needs one of the robustness extensions (or GL 4.5 / GLES 3.2).
+pub fn load_reset_status(video: &VideoSubsystem) -> Option<GetGraphicsResetStatusFn> {
+	let candidates: &[(&str, &str)] = &[
+		("GL_KHR_robustness", "glGetGraphicsResetStatus"),
+		("GL_KHR_robustness", "glGetGraphicsResetStatusKHR"),
+		("GL_ARB_robustness", "glGetGraphicsResetStatusARB"),
+		("GL_EXT_robustness", "glGetGraphicsResetStatusEXT"),
+	];
+
+	let get_reset_status = candidates
+		.iter()
+		.filter(|&&(extension, _)| video.gl_extension_supported(extension))
+		.filter_map(|&(_, name)| unsafe { load::<GetGraphicsResetStatusFn>(video, &[name]) })
+		.next()?;
+
+	let get_integerv = unsafe { load::<GetIntegervFn>(video, &["glGetIntegerv"])? };
+	let mut strategy = 0;
+	get_integerv(RESET_NOTIFICATION_STRATEGY, &mut strategy);
+
+	// Don't leave an error behind for glium to find during its own initialization.
+	if let Some(get_error) = unsafe { load::<GetErrorFn>(video, &["glGetError"]) } {
+		for _ in 0..8 {
+			if get_error() == NO_ERROR {
+				break;
+			}
+		}
+	}
+
+	if strategy as GLenum == LOSE_CONTEXT_ON_RESET {
+		Some(get_reset_status)
+	} else {
+		None
+	}
+}
+
+/// Returns one of the strings describing the current context, such as `VENDOR`.
+pub fn get_string(video: &VideoSubsystem, name: GLenum) -> Option<String> {
+	let get_string = unsafe { load::<GetStringFn>(video, &["glGetString"])? };
+
+	let ptr = get_string(name);
+	if ptr.is_null() {
+		return None;
+	}
+
+	Some(unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned())
+}
+
+/// The entry points of timer queries, from GL 3.3, `GL_ARB_timer_query` or
+/// `GL_EXT_disjoint_timer_query`.
+#[derive(Copy, Clone)]
+pub struct TimerQueries {
+	pub gen_queries: GenQueriesFn,
+	pub begin_query: BeginQueryFn,
+	pub end_query: EndQueryFn,
+	pub get_query_objectiv: GetQueryObjectivFn,
+	pub get_query_objectui64v: GetQueryObjectui64vFn,
+
+	// only with `GL_EXT_disjoint_timer_query`, whose results are invalidated by GPU disjoint events
+	pub get_integerv: Option<GetIntegervFn>,
+}
+
+/// Loads the timer query entry points of the current context.
+///
+/// `disjoint` selects the names of `GL_EXT_disjoint_timer_query`, and must only be set if the
+/// context supports it. Otherwise the context must support GL 3.3 or `GL_ARB_timer_query`.
+pub fn load_timer_queries(video: &VideoSubsystem, disjoint: bool) -> Option<TimerQueries> {
+	let suffix = if disjoint { "EXT" } else { "" };
+	let name = |name: &str| format!("{}{}", name, suffix);
+
+	unsafe {
+		Some(TimerQueries {
+			gen_queries: load(video, &[&name("glGenQueries")])?,
+			begin_query: load(video, &[&name("glBeginQuery")])?,
+			end_query: load(video, &[&name("glEndQuery")])?,
+			get_query_objectiv: load(video, &[&name("glGetQueryObjectiv")])?,
+			get_query_objectui64v: load(video, &[&name("glGetQueryObjectui64v")])?,
+			get_integerv: if disjoint { Some(load(video, &["glGetIntegerv"])?) } else { None },
+		})
+	}
+}
+
+/// The entry points of fences, from GL 3.2, GLES 3.0 or `GL_ARB_sync`.
+///
+/// glium has fences of its own, but they can only be waited on, not polled.
+#[derive(Copy, Clone)]
+pub struct Fences {
+	pub fence_sync: FenceSyncFn,
+	pub client_wait_sync: ClientWaitSyncFn,
+	pub delete_sync: DeleteSyncFn,
+}
+
+/// Loads the fence entry points of the current context, which must support them.
+pub fn load_fences(video: &VideoSubsystem) -> Option<Fences> {
+	unsafe {
+		Some(Fences {
+			fence_sync: load(video, &["glFenceSync"])?,
+			client_wait_sync: load(video, &["glClientWaitSync"])?,
+			delete_sync: load(video, &["glDeleteSync"])?,
+		})
+	}
+}
+
+/// The entry points of framebuffer objects, from GL 3.0, GLES 2.0 or
+/// `GL_EXT_framebuffer_object`.
+#[derive(Copy, Clone)]
+pub struct FramebufferObjects {
+	pub gen_framebuffers: GenFramebuffersFn,
+	pub bind_framebuffer: BindFramebufferFn,
+	pub check_framebuffer_status: CheckFramebufferStatusFn,
+	pub gen_renderbuffers: GenRenderbuffersFn,
+	pub bind_renderbuffer: BindRenderbufferFn,
+	pub renderbuffer_storage: RenderbufferStorageFn,
+	pub framebuffer_renderbuffer: FramebufferRenderbufferFn,
+	pub get_integerv: GetIntegervFn,
+}
+
+/// Loads the framebuffer object entry points of the current context.
+pub fn load_framebuffer_objects(video: &VideoSubsystem) -> Option<FramebufferObjects> {
+	unsafe {
+		Some(FramebufferObjects {
+			gen_framebuffers: load(video, &["glGenFramebuffers", "glGenFramebuffersEXT"])?,
+			bind_framebuffer: load(video, &["glBindFramebuffer", "glBindFramebufferEXT"])?,
+			check_framebuffer_status: load(video, &["glCheckFramebufferStatus", "glCheckFramebufferStatusEXT"])?,
+			gen_renderbuffers: load(video, &["glGenRenderbuffers", "glGenRenderbuffersEXT"])?,
+			bind_renderbuffer: load(video, &["glBindRenderbuffer", "glBindRenderbufferEXT"])?,
+			renderbuffer_storage: load(video, &["glRenderbufferStorage", "glRenderbufferStorageEXT"])?,
+			framebuffer_renderbuffer: load(video, &["glFramebufferRenderbuffer", "glFramebufferRenderbufferEXT"])?,
+			get_integerv: load(video, &["glGetIntegerv"])?,
+		})
+	}
+}
@@ -0,0 +1,299 @@
+//! Framebuffer objects standing in for the default framebuffer of hidden windows.
+//!
+//! Only the windows of SDL's `offscreen` video driver have pixels of their own while hidden.
+//! With the other drivers, `build_glium_headless` draws on a framebuffer object instead. glium
+//! always draws frames on framebuffer 0, so the backend hands it entry points that redirect
+//! framebuffer 0, and the front and back buffers, to the framebuffer object of the window
+//! whose context is current. Deleting a bound framebuffer object binds that one again, where
+//! OpenGL would bind framebuffer 0.
+
+use std::cell::{Cell, RefCell};
+use std::os::raw::c_void;
+use std::slice;
+
+use sdl2::sys::{SDL_GL_GetCurrentWindow, SDL_Window};
+use sdl2::video::Window;
+use sdl2::VideoSubsystem;
+
+use config::FramebufferAttributes;
+use gl::{self, GLbitfield, GLenum, GLint, GLsizei, GLuint};
+
+/// The real entry points, called by the redirecting ones.
+#[derive(Copy, Clone, Default)]
+struct EntryPoints {
+	bind_framebuffer: Option<gl::BindFramebufferFn>,
+	bind_framebuffer_ext: Option<gl::BindFramebufferFn>,
+	delete_framebuffers: Option<gl::DeleteFramebuffersFn>,
+	delete_framebuffers_ext: Option<gl::DeleteFramebuffersFn>,
+	blit_named_framebuffer: Option<gl::BlitNamedFramebufferFn>,
+	read_buffer: Option<gl::ReadBufferFn>,
+	draw_buffer: Option<gl::DrawBufferFn>,
+}
+
+thread_local! {
+	// OpenGL contexts are current on a single thread, and so are the facades using them
+	static ENTRY_POINTS: Cell<EntryPoints> = Cell::new(EntryPoints::default());
+	static REDIRECTS: RefCell<Vec<Redirect>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The framebuffer object of a headless window, and what its context has bound.
+struct Redirect {
+	window: *mut SDL_Window,
+	framebuffer: GLuint,
+
+	// the framebuffers bound for drawing and for reading
+	bound: Cell<(GLuint, GLuint)>,
+}
+
+impl Redirect {
+	fn bind(&self, target: GLenum, framebuffer: GLuint) {
+		let (draw, read) = self.bound.get();
+		self.bound.set(match target {
+			gl::DRAW_FRAMEBUFFER => (framebuffer, read),
+			gl::READ_FRAMEBUFFER => (draw, framebuffer),
+			_ => (framebuffer, framebuffer),
+		});
+	}
+}
+
+/// The framebuffer object drawn on in place of a window's default framebuffer.
+///
+/// The objects are deleted along with the window's context.
+pub(crate) struct HeadlessFramebuffer {
+	window: *mut SDL_Window,
+	functions: gl::FramebufferObjects,
+	color: GLuint,
+	depth_stencil: GLuint,
+	dimensions: Cell<(u32, u32)>,
+}
+
+impl HeadlessFramebuffer {
+	/// Creates a framebuffer object with the window's drawable size and binds it.
+	///
+	/// The window's context must be current, and glium must not have been started on it yet,
+	/// as glium assumes that the default framebuffer is bound when it starts.
+	pub(crate) fn new(window: &Window) -> Result<HeadlessFramebuffer, String> {
+		let video = window.subsystem();
+		let functions = gl::load_framebuffer_objects(video)
+			.ok_or_else(|| "the OpenGL context doesn't support framebuffer objects".to_owned())?;
+		load_entry_points(video);
+
+		let (mut framebuffer, mut renderbuffers) = (0, [0; 2]);
+		(functions.gen_framebuffers)(1, &mut framebuffer);
+		(functions.gen_renderbuffers)(2, renderbuffers.as_mut_ptr());
+
+		let headless = HeadlessFramebuffer {
+			window: window.raw(),
+			functions,
+			color: renderbuffers[0],
+			depth_stencil: renderbuffers[1],
+			dimensions: Cell::new((0, 0)),
+		};
+		headless.resize(window.drawable_size());
+
+		(functions.bind_framebuffer)(gl::FRAMEBUFFER, framebuffer);
+		(functions.framebuffer_renderbuffer)(
+			gl::FRAMEBUFFER,
+			gl::COLOR_ATTACHMENT0,
+			gl::RENDERBUFFER,
+			headless.color,
+		);
+		for &attachment in &[gl::DEPTH_ATTACHMENT, gl::STENCIL_ATTACHMENT] {
+			(functions.framebuffer_renderbuffer)(
+				gl::FRAMEBUFFER,
+				attachment,
+				gl::RENDERBUFFER,
+				headless.depth_stencil,
+			);
+		}
+
+		let status = (functions.check_framebuffer_status)(gl::FRAMEBUFFER);
+		if status != gl::FRAMEBUFFER_COMPLETE {
+			(functions.bind_framebuffer)(gl::FRAMEBUFFER, 0);
+			return Err(format!("the framebuffer object of the hidden window is incomplete (0x{:04X})", status));
+		}
+
+		let redirect =
+			Redirect { window: headless.window, framebuffer, bound: Cell::new((framebuffer, framebuffer)) };
+		REDIRECTS.with(|redirects| redirects.borrow_mut().push(redirect));
+		Ok(headless)
+	}
+
+	/// The attributes of the framebuffer object, as reported by `SDL2Facade::context_attributes`.
+	pub(crate) fn attributes() -> FramebufferAttributes {
+		// drawing and reading both happen on the single color buffer
+		FramebufferAttributes { depth_bits: 24, stencil_bits: 8, multisample_samples: 0, double_buffer: false }
+	}
+
+	/// Reallocates the buffers if the dimensions changed. The window's context must be current.
+	pub(crate) fn resize(&self, dimensions: (u32, u32)) {
+		if self.dimensions.replace(dimensions) == dimensions {
+			return;
+		}
+
+		let functions = &self.functions;
+		let (width, height) = (dimensions.0.max(1) as gl::GLsizei, dimensions.1.max(1) as gl::GLsizei);
+
+		// glium caches the renderbuffer binding, so it's put back as it was
+		let mut previous = 0;
+		(functions.get_integerv)(gl::RENDERBUFFER_BINDING, &mut previous);
+		for &(renderbuffer, format) in &[(self.color, gl::RGBA8), (self.depth_stencil, gl::DEPTH24_STENCIL8)] {
+			(functions.bind_renderbuffer)(gl::RENDERBUFFER, renderbuffer);
+			(functions.renderbuffer_storage)(gl::RENDERBUFFER, format, width, height);
+		}
+		(functions.bind_renderbuffer)(gl::RENDERBUFFER, previous as GLuint);
+	}
+}
+
+impl Drop for HeadlessFramebuffer {
+	fn drop(&mut self) {
+		REDIRECTS.with(|redirects| redirects.borrow_mut().retain(|redirect| redirect.window != self.window));
+	}
+}
+
+/// Returns the entry point redirecting `symbol`, if it's one that uses the default framebuffer.
+pub(crate) fn redirected_entry_point(symbol: &str) -> Option<*const c_void> {
+	let entry_points = ENTRY_POINTS.with(Cell::get);
+	let redirected = match symbol {
+		"glBindFramebuffer" if entry_points.bind_framebuffer.is_some() => bind_framebuffer as *const c_void,
+		"glBindFramebufferEXT" if entry_points.bind_framebuffer_ext.is_some() => {
+			bind_framebuffer_ext as *const c_void
+		}
+		"glDeleteFramebuffers" if entry_points.delete_framebuffers.is_some() => {
+			delete_framebuffers as *const c_void
+		}
+		"glDeleteFramebuffersEXT" if entry_points.delete_framebuffers_ext.is_some() => {
+			delete_framebuffers_ext as *const c_void
+		}
+		"glBlitNamedFramebuffer" if entry_points.blit_named_framebuffer.is_some() => {
+			blit_named_framebuffer as *const c_void
+		}
+		"glReadBuffer" if entry_points.read_buffer.is_some() => read_buffer as *const c_void,
+		"glDrawBuffer" if entry_points.draw_buffer.is_some() => draw_buffer as *const c_void,
+		_ => return None,
+	};
+
+	Some(redirected)
+}
+
+fn load_entry_points(video: &VideoSubsystem) {
+	let entry_points = unsafe {
+		EntryPoints {
+			bind_framebuffer: gl::load(video, &["glBindFramebuffer"]),
+			bind_framebuffer_ext: gl::load(video, &["glBindFramebufferEXT"]),
+			delete_framebuffers: gl::load(video, &["glDeleteFramebuffers"]),
+			delete_framebuffers_ext: gl::load(video, &["glDeleteFramebuffersEXT"]),
+			blit_named_framebuffer: gl::load(video, &["glBlitNamedFramebuffer"]),
+			read_buffer: gl::load(video, &["glReadBuffer"]),
+			draw_buffer: gl::load(video, &["glDrawBuffer"]),
+		}
+	};
+	ENTRY_POINTS.with(|cell| cell.set(entry_points));
+}
+
+/// Calls `f` with the redirect of the window whose context is current, if it has one.
+fn with_current_redirect<R, F: FnOnce(&Redirect) -> R>(f: F) -> Option<R> {
+	let window = unsafe { SDL_GL_GetCurrentWindow() };
+	REDIRECTS.with(|redirects| redirects.borrow().iter().find(|redirect| redirect.window == window).map(f))
+}
+
+fn redirect_framebuffer(framebuffer: GLuint) -> GLuint {
+	match framebuffer {
+		0 => with_current_redirect(|redirect| redirect.framebuffer).unwrap_or(0),
+		framebuffer => framebuffer,
+	}
+}
+
+/// Replaces the buffers of the default framebuffer with the color buffer of the framebuffer
+/// object, as binding framebuffer 0 binds it instead.
+fn redirect_buffer(buffer: GLenum) -> GLenum {
+	if (gl::FRONT_LEFT..=gl::FRONT_AND_BACK).contains(&buffer) && with_current_redirect(|_| ()).is_some() {
+		gl::COLOR_ATTACHMENT0
+	} else {
+		buffer
+	}
+}
+
+fn bind(bind_framebuffer: gl::BindFramebufferFn, target: GLenum, framebuffer: GLuint) {
+	let framebuffer = redirect_framebuffer(framebuffer);
+	bind_framebuffer(target, framebuffer);
+	with_current_redirect(|redirect| redirect.bind(target, framebuffer));
+}
+
+extern "system" fn bind_framebuffer(target: GLenum, framebuffer: GLuint) {
+	if let Some(bind_framebuffer) = ENTRY_POINTS.with(Cell::get).bind_framebuffer {
+		bind(bind_framebuffer, target, framebuffer);
+	}
+}
+
+extern "system" fn bind_framebuffer_ext(target: GLenum, framebuffer: GLuint) {
+	if let Some(bind_framebuffer) = ENTRY_POINTS.with(Cell::get).bind_framebuffer_ext {
+		bind(bind_framebuffer, target, framebuffer);
+	}
+}
+
+/// Deletes framebuffer objects, binding the framebuffer object of the window again where one
+/// of them was bound, as OpenGL binds framebuffer 0 in its place.
+fn delete(
+	delete_framebuffers: gl::DeleteFramebuffersFn,
+	bind_framebuffer: Option<gl::BindFramebufferFn>,
+	count: GLsizei,
+	framebuffers: *const GLuint,
+) {
+	let deleted = unsafe { slice::from_raw_parts(framebuffers, count.max(0) as usize) };
+	let (draw, read) = with_current_redirect(|redirect| redirect.bound.get()).unwrap_or((0, 0));
+	let target = match (deleted.contains(&draw), deleted.contains(&read)) {
+		(true, true) => Some(gl::FRAMEBUFFER),
+		(true, false) => Some(gl::DRAW_FRAMEBUFFER),
+		(false, true) => Some(gl::READ_FRAMEBUFFER),
+		(false, false) => None,
+	};
+
+	delete_framebuffers(count, framebuffers);
+
+	if let (Some(target), Some(bind_framebuffer)) = (target, bind_framebuffer) {
+		bind(bind_framebuffer, target, 0);
+	}
+}
+
+extern "system" fn delete_framebuffers(count: GLsizei, framebuffers: *const GLuint) {
+	let entry_points = ENTRY_POINTS.with(Cell::get);
+	if let Some(delete_framebuffers) = entry_points.delete_framebuffers {
+		delete(delete_framebuffers, entry_points.bind_framebuffer, count, framebuffers);
+	}
+}
+
+extern "system" fn delete_framebuffers_ext(count: GLsizei, framebuffers: *const GLuint) {
+	let entry_points = ENTRY_POINTS.with(Cell::get);
+	if let Some(delete_framebuffers) = entry_points.delete_framebuffers_ext {
+		delete(delete_framebuffers, entry_points.bind_framebuffer_ext, count, framebuffers);
+	}
+}
+
+#[allow(clippy::too_many_arguments)]
+#[rustfmt::skip]
+extern "system" fn blit_named_framebuffer(
+	source: GLuint, target: GLuint,
+	src_x0: GLint, src_y0: GLint, src_x1: GLint, src_y1: GLint,
+	dst_x0: GLint, dst_y0: GLint, dst_x1: GLint, dst_y1: GLint,
+	mask: GLbitfield, filter: GLenum,
+) {
+	if let Some(blit_named_framebuffer) = ENTRY_POINTS.with(Cell::get).blit_named_framebuffer {
+		blit_named_framebuffer(
+			redirect_framebuffer(source), redirect_framebuffer(target),
+			src_x0, src_y0, src_x1, src_y1, dst_x0, dst_y0, dst_x1, dst_y1, mask, filter,
+		);
+	}
+}
+
+extern "system" fn read_buffer(buffer: GLenum) {
+	if let Some(read_buffer) = ENTRY_POINTS.with(Cell::get).read_buffer {
+		read_buffer(redirect_buffer(buffer));
+	}
+}
+
+extern "system" fn draw_buffer(buffer: GLenum) {
+	if let Some(draw_buffer) = ENTRY_POINTS.with(Cell::get).draw_buffer {
+		draw_buffer(redirect_buffer(buffer));
+	}
+}
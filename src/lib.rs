@@ -39,6 +39,8 @@
 //!     for event in event_pump.poll_iter() {
 //!         use sdl2::event::Event;
 //!
+//!         display.handle_event(&event);
+//!
 //!         match event {
 //!             Event::Quit { .. } => {
 //!                 running = false;
@@ -53,24 +55,151 @@
 extern crate glium;
 extern crate sdl2;
 
-use std::cell::UnsafeCell;
+#[cfg(feature = "image")]
+extern crate image;
+#[cfg(feature = "serde")]
+extern crate serde;
+
+mod config;
+mod cursor;
+mod diagnostics;
+mod dpi;
+mod game_loop;
+mod gl;
+mod headless;
+mod readback;
+mod recorder;
+mod screenshot;
+mod surface;
+mod timing;
+mod virtual_frame;
+mod window;
+
+use std::cell::{BorrowError, BorrowMutError, Cell, RefCell};
 use std::ops::Deref;
 use std::os::raw::c_void;
 use std::rc::Rc;
 
 use glium::backend::{Backend, Context, Facade};
 use glium::debug;
+use glium::CapabilitiesSource;
 use glium::IncompatibleOpenGl;
 use glium::SwapBuffersError;
-use sdl2::video::{Window, WindowBuildError};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::rect::Rect;
+use sdl2::video::{SwapInterval, Window, WindowBuildError};
 use sdl2::VideoSubsystem;
 
+pub use config::{AttributeMismatch, ContextAttributes, GlConfig};
+pub use cursor::CursorHandle;
+pub use diagnostics::{facade_diagnostics, ContextDescription, Diagnostics};
+pub use game_loop::{Clock, Game, GameLoop, LoopControl, Pacing, SystemClock, Tick};
+pub use readback::{AsyncReadback, ReadbackFrame};
+pub use recorder::FrameRecorder;
+pub use screenshot::Screenshot;
+pub use surface::{surface_from_texture, texture_from_surface};
+pub use timing::{DurationStats, FrameStats, TimedFrame};
+pub use virtual_frame::{ScaleMode, VirtualFrame, VirtualViewport};
+pub use window::{WindowRef, WindowRefMut};
+
 pub type Display = SDL2Facade;
 
+/// Requests that the OpenGL contexts created from now on report GPU resets to glium.
+///
+/// This sets SDL's robust access context flag, on top of the flags already set, and asks the
+/// driver to lose the context on reset. Contexts created this way are checked for resets after
+/// every buffer swap, so that `Frame::finish` returns `SwapBuffersError::ContextLost` once
+/// the context needs to be recreated.
+///
+/// `reset_isolation` additionally asks that resets caused by other applications don't affect
+/// this one. Few drivers support it, and context creation fails on those that don't.
+///
+/// SDL only passes the reset strategy on to GLX and WGL. Contexts created through EGL, as on
+/// Wayland or with the `offscreen` driver, are robust but may not report resets, which
+/// `SDL2Facade::detects_context_loss` tells.
+///
+/// Call this before `build_glium`.
+pub fn request_robust_context(video: &VideoSubsystem, reset_isolation: bool) -> Result<(), String> {
+	let gl_attr = video.gl_attr();
+	let flags = gl_attr.context_flags();
+
+	let mut builder = gl_attr.set_context_flags();
+	if flags.has_debug() {
+		builder.debug();
+	}
+	if flags.has_forward_compatible() {
+		builder.forward_compatible();
+	}
+	if reset_isolation || flags.has_reset_isolation() {
+		builder.reset_isolation();
+	}
+	builder.robust_access().set();
+
+	config::set_reset_notification(true)
+}
+
+/// Error that can happen while building or using a facade.
+///
+/// The errors of SDL and glium that caused it are available through `Error::source`.
+/// When building a facade fails, `facade_diagnostics` tells more about the system.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum GliumSdl2Error {
+	/// SDL couldn't build the window.
 	WindowBuildError(WindowBuildError),
+
+	/// SDL couldn't create the OpenGL context, or set the attributes to create it with.
 	ContextCreationError(String),
+
+	/// SDL couldn't make the OpenGL context current.
+	MakeCurrentFailed(String),
+
+	/// The OpenGL context was created, but glium can't use it.
+	IncompatibleOpenGl(IncompatibleOpenGl),
+
+	/// The OpenGL context doesn't have the requested attributes (see `build_glium_strict`), or
+	/// those of the context it replaces (see `rebuild_glium`).
+	AttributeMismatch(Vec<AttributeMismatch>),
+
+	/// The video subsystem can't provide OpenGL.
+	VideoInitError(String),
+
+	/// SDL couldn't set the swap interval.
+	SwapIntervalError(String),
+
+	/// glium couldn't read pixels back.
+	ReadError(glium::ReadError),
+
+	/// glium couldn't create a texture that the facade needed.
+	TextureCreationError(glium::texture::TextureCreationError),
+
+	/// glium couldn't read a buffer back, such as the pixel buffer of an `AsyncReadback`.
+	BufferReadError(glium::buffer::ReadError),
+
+	/// glium couldn't compile the shaders that the facade needed.
+	ProgramCreationError(glium::ProgramCreationError),
+
+	/// glium couldn't create a vertex buffer that the facade needed.
+	VertexBufferCreationError(glium::vertex::BufferCreationError),
+
+	/// glium couldn't draw.
+	DrawError(glium::DrawError),
+
+	/// Something couldn't be written to a file or a stream.
+	IoError(std::io::Error),
+
+	/// SDL couldn't create or convert a surface.
+	SurfaceError(String),
+
+	/// SDL couldn't create a cursor.
+	CursorError(String),
+
+	/// The OpenGL context doesn't support fences (GL 3.2, GLES 3.0 or `GL_ARB_sync`).
+	SyncNotSupported,
+
+	/// An image couldn't be encoded or written.
+	#[cfg(feature = "image")]
+	ImageError(image::ImageError),
 }
 
 impl From<String> for GliumSdl2Error {
@@ -87,15 +216,74 @@ impl From<WindowBuildError> for GliumSdl2Error {
 
 impl From<IncompatibleOpenGl> for GliumSdl2Error {
 	fn from(err: IncompatibleOpenGl) -> GliumSdl2Error {
-		GliumSdl2Error::ContextCreationError(err.to_string())
+		GliumSdl2Error::IncompatibleOpenGl(err)
+	}
+}
+
+impl From<glium::ReadError> for GliumSdl2Error {
+	fn from(err: glium::ReadError) -> GliumSdl2Error {
+		GliumSdl2Error::ReadError(err)
+	}
+}
+
+impl From<glium::texture::TextureCreationError> for GliumSdl2Error {
+	fn from(err: glium::texture::TextureCreationError) -> GliumSdl2Error {
+		GliumSdl2Error::TextureCreationError(err)
+	}
+}
+
+impl From<glium::buffer::ReadError> for GliumSdl2Error {
+	fn from(err: glium::buffer::ReadError) -> GliumSdl2Error {
+		GliumSdl2Error::BufferReadError(err)
+	}
+}
+
+impl From<glium::ProgramCreationError> for GliumSdl2Error {
+	fn from(err: glium::ProgramCreationError) -> GliumSdl2Error {
+		GliumSdl2Error::ProgramCreationError(err)
+	}
+}
+
+impl From<glium::vertex::BufferCreationError> for GliumSdl2Error {
+	fn from(err: glium::vertex::BufferCreationError) -> GliumSdl2Error {
+		GliumSdl2Error::VertexBufferCreationError(err)
+	}
+}
+
+impl From<glium::DrawError> for GliumSdl2Error {
+	fn from(err: glium::DrawError) -> GliumSdl2Error {
+		GliumSdl2Error::DrawError(err)
+	}
+}
+
+impl From<std::io::Error> for GliumSdl2Error {
+	fn from(err: std::io::Error) -> GliumSdl2Error {
+		GliumSdl2Error::IoError(err)
 	}
 }
 
 impl std::error::Error for GliumSdl2Error {
-	fn cause(&self) -> Option<&dyn std::error::Error> {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
 		match *self {
-			GliumSdl2Error::WindowBuildError(ref err) => err.source(),
-			GliumSdl2Error::ContextCreationError(_) => None,
+			GliumSdl2Error::WindowBuildError(ref err) => Some(err),
+			GliumSdl2Error::IncompatibleOpenGl(ref err) => Some(err),
+			GliumSdl2Error::ReadError(ref err) => Some(err),
+			GliumSdl2Error::TextureCreationError(ref err) => Some(err),
+			GliumSdl2Error::BufferReadError(ref err) => Some(err),
+			GliumSdl2Error::ProgramCreationError(ref err) => Some(err),
+			GliumSdl2Error::VertexBufferCreationError(ref err) => Some(err),
+			GliumSdl2Error::DrawError(ref err) => Some(err),
+			GliumSdl2Error::IoError(ref err) => Some(err),
+			#[cfg(feature = "image")]
+			GliumSdl2Error::ImageError(ref err) => Some(err),
+			GliumSdl2Error::ContextCreationError(_)
+			| GliumSdl2Error::MakeCurrentFailed(_)
+			| GliumSdl2Error::AttributeMismatch(_)
+			| GliumSdl2Error::VideoInitError(_)
+			| GliumSdl2Error::SwapIntervalError(_)
+			| GliumSdl2Error::SurfaceError(_)
+			| GliumSdl2Error::CursorError(_)
+			| GliumSdl2Error::SyncNotSupported => None,
 		}
 	}
 }
@@ -103,12 +291,127 @@ impl std::error::Error for GliumSdl2Error {
 impl std::fmt::Display for GliumSdl2Error {
 	fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
 		match *self {
-			GliumSdl2Error::WindowBuildError(ref err) => err.fmt(formatter),
-			GliumSdl2Error::ContextCreationError(ref err) => err.fmt(formatter),
+			GliumSdl2Error::WindowBuildError(_) => write!(formatter, "could not build the window"),
+			GliumSdl2Error::ContextCreationError(ref err) => {
+				write!(formatter, "could not create the OpenGL context: {}", err)
+			}
+			GliumSdl2Error::MakeCurrentFailed(ref err) => {
+				write!(formatter, "could not make the OpenGL context current: {}", err)
+			}
+			GliumSdl2Error::IncompatibleOpenGl(_) => {
+				write!(formatter, "the OpenGL implementation is not supported by glium")
+			}
+			GliumSdl2Error::AttributeMismatch(ref mismatches) => {
+				write!(formatter, "the OpenGL context doesn't have the requested attributes")?;
+				for (i, mismatch) in mismatches.iter().enumerate() {
+					write!(formatter, "{} {}", if i == 0 { ":" } else { ";" }, mismatch)?;
+				}
+				Ok(())
+			}
+			GliumSdl2Error::VideoInitError(ref err) => write!(formatter, "could not initialize video: {}", err),
+			GliumSdl2Error::SwapIntervalError(ref err) => {
+				write!(formatter, "could not set the swap interval: {}", err)
+			}
+			GliumSdl2Error::ReadError(_) => write!(formatter, "could not read the framebuffer"),
+			GliumSdl2Error::TextureCreationError(_) => write!(formatter, "could not create a texture"),
+			GliumSdl2Error::BufferReadError(_) => write!(formatter, "could not read a buffer"),
+			GliumSdl2Error::ProgramCreationError(_) => write!(formatter, "could not compile a shader program"),
+			GliumSdl2Error::VertexBufferCreationError(_) => write!(formatter, "could not create a vertex buffer"),
+			GliumSdl2Error::DrawError(_) => write!(formatter, "could not draw"),
+			GliumSdl2Error::IoError(_) => write!(formatter, "could not write the output"),
+			GliumSdl2Error::SurfaceError(ref err) => write!(formatter, "could not convert the surface: {}", err),
+			GliumSdl2Error::CursorError(ref err) => write!(formatter, "could not create the cursor: {}", err),
+			GliumSdl2Error::SyncNotSupported => write!(formatter, "the OpenGL context doesn't support fences"),
+			#[cfg(feature = "image")]
+			GliumSdl2Error::ImageError(_) => write!(formatter, "could not write the image"),
 		}
 	}
 }
 
+/// Error returned by `SDL2Facade::close` when something else still uses the facade.
+///
+/// The facade is left untouched and can be taken back with `into_facade`.
+pub struct CloseError {
+	facade: SDL2Facade,
+	clones: usize,
+	objects: usize,
+}
+
+impl CloseError {
+	/// The number of other clones of the facade that are still alive.
+	pub fn outstanding_clones(&self) -> usize {
+		self.clones
+	}
+
+	/// The number of glium objects (buffers, textures, programs, frames...) that still refer
+	/// to the facade's context.
+	pub fn outstanding_objects(&self) -> usize {
+		self.objects
+	}
+
+	/// Gives back the facade that couldn't be closed.
+	pub fn into_facade(self) -> SDL2Facade {
+		self.facade
+	}
+}
+
+impl std::fmt::Debug for CloseError {
+	fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		formatter
+			.debug_struct("CloseError")
+			.field("clones", &self.clones)
+			.field("objects", &self.objects)
+			.finish()
+	}
+}
+
+impl std::error::Error for CloseError {}
+
+impl std::fmt::Display for CloseError {
+	fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		write!(
+			formatter,
+			"the facade can't be closed while {} other clone(s) and {} glium object(s) still use it",
+			self.clones, self.objects
+		)
+	}
+}
+
+/// What to do when glium needs the OpenGL context to be current and SDL fails to make it so.
+///
+/// Use `SDL2Facade::try_make_current` to handle the error yourself instead.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum MakeCurrentPolicy {
+	/// Panic with the SDL error message. This is the default.
+	#[default]
+	Panic,
+
+	/// Print the SDL error message to stderr and don't present the frame being drawn.
+	///
+	/// The next `swap_buffers` is skipped, so that a frame drawn without its context doesn't
+	/// get shown. The error is printed once per skipped frame, although glium tries again
+	/// before each of its commands. Drawing resumes normally once the context can be made
+	/// current again.
+	///
+	/// No context is current while this one can't be made current, so that the commands of the
+	/// skipped frame are dropped rather than run on the context of another facade.
+	LogAndSkipFrame,
+}
+
+/// Whether buffer swaps wait for the display's vertical refresh.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Vsync {
+	/// Buffers are swapped immediately, which may cause tearing.
+	Off,
+
+	/// Buffer swaps wait for the vertical refresh.
+	On,
+
+	/// Buffer swaps wait for the vertical refresh, unless the frame is late, in which case
+	/// it's swapped immediately. Also known as late swap tearing.
+	Adaptive,
+}
+
 /// Facade implementation for an SDL2 window.
 #[derive(Clone)]
 pub struct SDL2Facade {
@@ -116,6 +419,12 @@ pub struct SDL2Facade {
 	context: Rc<Context>,
 
 	backend: Rc<SDL2WindowBackend>,
+
+	// the target of `draw_virtual`, kept from one frame to the next and shared by the clones
+	virtual_texture: Rc<RefCell<Option<glium::Texture2d>>>,
+
+	// only held by the facade and its clones, to count them in `close`
+	clones: Rc<()>,
 }
 
 impl Facade for SDL2Facade {
@@ -133,12 +442,337 @@ impl Deref for SDL2Facade {
 }
 
 impl SDL2Facade {
-	pub fn window(&self) -> &Window {
-		self.backend.window()
+	fn new(context: Rc<Context>, backend: Rc<SDL2WindowBackend>) -> SDL2Facade {
+		SDL2Facade { context, backend, virtual_texture: Default::default(), clones: Default::default() }
+	}
+
+	/// Builds a facade that draws on an existing window, creating an OpenGL context for it.
+	///
+	/// The window must have been built with the `opengl` flag. The context is created with
+	/// the OpenGL attributes currently set on the video subsystem.
+	///
+	/// Performs a compatibility check to make sure that all core elements of glium
+	/// are supported by the implementation.
+	pub fn from_window(window: Window) -> Result<SDL2Facade, GliumSdl2Error> {
+		let backend = Rc::new(SDL2WindowBackend::from_window(window)?);
+		let context = unsafe { Context::new(backend.clone(), true, Default::default())? };
+
+		Ok(SDL2Facade::new(context, backend))
+	}
+
+	/// Builds a facade that draws on an existing window with an existing OpenGL context.
+	///
+	/// Performs a compatibility check to make sure that all core elements of glium
+	/// are supported by the implementation.
+	///
+	/// # Safety
+	///
+	/// The context must have been created for this window, or for one with the same pixel
+	/// format. glium takes over the context's state, so it must not be used by anything else
+	/// while the facade is alive.
+	pub unsafe fn from_window_and_context(
+		window: Window,
+		gl_context: sdl2::video::GLContext,
+	) -> Result<SDL2Facade, GliumSdl2Error> {
+		let backend = Rc::new(SDL2WindowBackend::from_window_and_context(window, gl_context)?);
+		let context = Context::new(backend.clone(), true, Default::default())?;
+
+		Ok(SDL2Facade::new(context, backend))
+	}
+
+	/// Destroys glium's context and the OpenGL context, and gives back the window.
+	///
+	/// This only works on the last clone of the facade once every glium object created from
+	/// it (buffers, textures, programs...) has been dropped. Otherwise the facade is given
+	/// back unchanged.
+	pub fn into_window(self) -> Result<Window, SDL2Facade> {
+		self.virtual_texture.borrow_mut().take();
+		if Rc::strong_count(&self.context) != 1 {
+			return Err(self);
+		}
+
+		Ok(self.tear_down())
+	}
+
+	/// Waits for the GPU and destroys, in order, glium's context, the OpenGL context and the
+	/// window.
+	///
+	/// Dropping the last clone of a facade does the same, but glium objects that outlive it
+	/// keep the OpenGL context alive, and the GPU may still be working when they go. This
+	/// fails instead, listing what still uses the facade.
+	pub fn close(self) -> Result<(), CloseError> {
+		self.virtual_texture.borrow_mut().take();
+
+		// every clone holds the context, and so does every glium object
+		let clones = Rc::strong_count(&self.clones) - 1;
+		let objects = Rc::strong_count(&self.context) - 1 - clones;
+		if clones != 0 || objects != 0 {
+			return Err(CloseError { facade: self, clones, objects });
+		}
+
+		self.context.finish();
+		drop(self.tear_down());
+
+		Ok(())
+	}
+
+	/// Destroys the contexts and returns the window. This must be the last user of the context.
+	fn tear_down(self) -> Window {
+		let SDL2Facade { context, backend, virtual_texture, .. } = self;
+		drop(virtual_texture);
+		// glium deletes its objects when the context is dropped, so the context must still exist
+		drop(context);
+
+		let backend = match Rc::try_unwrap(backend) {
+			Ok(backend) => backend,
+			Err(_) => unreachable!("glium kept a reference to the backend after its context was dropped"),
+		};
+		let SDL2WindowBackend { context, window, .. } = backend;
+		drop(context);
+
+		window.into_inner()
+	}
+
+	/// Borrows the window.
+	///
+	/// # Panics
+	///
+	/// Panics if the window is mutably borrowed, through this facade or one of its clones.
+	pub fn window(&self) -> WindowRef<'_> {
+		WindowRef { window: self.backend.window.lent().borrow() }
+	}
+
+	/// Borrows the window, or returns an error if it's mutably borrowed.
+	pub fn try_window(&self) -> Result<WindowRef<'_>, BorrowError> {
+		self.backend.window.lent().try_borrow().map(|window| WindowRef { window })
+	}
+
+	/// Mutably borrows the window.
+	///
+	/// glium can keep drawing while the window is borrowed.
+	///
+	/// # Panics
+	///
+	/// Panics if the window is borrowed, through this facade or one of its clones.
+	pub fn window_mut(&self) -> WindowRefMut<'_> {
+		WindowRefMut {
+			window: self.backend.window.lent().borrow_mut(),
+			framebuffer_dimensions: &self.backend.framebuffer_dimensions,
+		}
+	}
+
+	/// Mutably borrows the window, or returns an error if it's borrowed.
+	pub fn try_window_mut(&self) -> Result<WindowRefMut<'_>, BorrowMutError> {
+		let framebuffer_dimensions = &self.backend.framebuffer_dimensions;
+		self.backend.window.lent().try_borrow_mut().map(|window| WindowRefMut { window, framebuffer_dimensions })
+	}
+
+	/// Returns the attributes that the driver actually gave to this facade's context.
+	pub fn context_attributes(&self) -> ContextAttributes {
+		let capabilities = self.get_capabilities();
+		let framebuffer = self.backend.framebuffer_attributes.get();
+
+		ContextAttributes {
+			version: *self.context.get_opengl_version(),
+			profile: capabilities.profile,
+			debug: capabilities.debug,
+			forward_compatible: capabilities.forward_compatible,
+			robust: capabilities.robustness,
+			depth_bits: framebuffer.depth_bits,
+			stencil_bits: framebuffer.stencil_bits,
+			multisample_samples: framebuffer.multisample_samples,
+			srgb: capabilities.srgb,
+			double_buffer: framebuffer.double_buffer,
+			vendor: capabilities.vendor.clone(),
+			renderer: capabilities.renderer.clone(),
+			version_string: capabilities.version.clone(),
+		}
+	}
+
+	/// Returns a diagnostic report on the system and on this facade's context.
+	pub fn diagnostics(&self) -> Result<Diagnostics, GliumSdl2Error> {
+		self.try_make_current()?;
+		Ok(Diagnostics::with_current_context(self.backend.subsystem()))
+	}
+
+	/// Makes this facade's OpenGL context the current one on this thread.
+	///
+	/// glium does this automatically whenever it needs to, but can't report failures, which are
+	/// handled according to the `MakeCurrentPolicy` instead.
+	pub fn try_make_current(&self) -> Result<(), GliumSdl2Error> {
+		self.backend.try_make_current()
+	}
+
+	/// Changes whether buffer swaps of this facade wait for the vertical refresh.
+	///
+	/// Adaptive vsync isn't supported everywhere, in which case this falls back to regular
+	/// vsync. Returns the mode that was applied.
+	pub fn set_vsync(&self, vsync: Vsync) -> Result<Vsync, GliumSdl2Error> {
+		// the swap interval is a property of the current context
+		self.try_make_current()?;
+
+		let subsystem = self.backend.subsystem();
+		let result = match vsync {
+			Vsync::Off => subsystem.gl_set_swap_interval(SwapInterval::Immediate).map(|()| Vsync::Off),
+			Vsync::On => subsystem.gl_set_swap_interval(SwapInterval::VSync).map(|()| Vsync::On),
+			Vsync::Adaptive => subsystem
+				.gl_set_swap_interval(SwapInterval::LateSwapTearing)
+				.map(|()| Vsync::Adaptive)
+				.or_else(|_| subsystem.gl_set_swap_interval(SwapInterval::VSync).map(|()| Vsync::On)),
+		};
+
+		result.map_err(GliumSdl2Error::SwapIntervalError)
+	}
+
+	/// Returns whether buffer swaps of this facade wait for the vertical refresh, as reported
+	/// by the driver.
+	pub fn vsync(&self) -> Result<Vsync, GliumSdl2Error> {
+		self.try_make_current()?;
+
+		// not `VideoSubsystem::gl_get_swap_interval`, which panics on intervals above 1
+		let interval = unsafe { sdl2::sys::SDL_GL_GetSwapInterval() };
+		Ok(match interval {
+			0 => Vsync::Off,
+			interval if interval < 0 => Vsync::Adaptive,
+			_ => Vsync::On,
+		})
+	}
+
+	/// Returns whether `Frame::finish` checks for GPU resets, which it does on contexts that
+	/// report them (see `request_robust_context`).
+	pub fn detects_context_loss(&self) -> bool {
+		self.backend.get_reset_status.get().is_some()
+	}
+
+	/// Returns what happens when glium fails to make this facade's context current.
+	pub fn make_current_policy(&self) -> MakeCurrentPolicy {
+		self.backend.make_current_policy.get()
+	}
+
+	/// Changes what happens when glium fails to make this facade's context current.
+	pub fn set_make_current_policy(&self, policy: MakeCurrentPolicy) {
+		self.backend.make_current_policy.set(policy)
+	}
+
+	/// Returns the size of the window in window units, which are the units of mouse events
+	/// and of `Window::size`.
+	pub fn logical_size(&self) -> (u32, u32) {
+		self.backend.window.handle().size()
+	}
+
+	/// Returns the size of the window's framebuffer in pixels, which are the units of glium.
+	///
+	/// This is larger than `logical_size` on HiDPI displays, provided the window was built with
+	/// `allow_highdpi` (see `GlConfig::high_dpi`).
+	pub fn drawable_size(&self) -> (u32, u32) {
+		self.backend.window.handle().drawable_size()
+	}
+
+	/// Returns the number of framebuffer pixels per window unit, such as 2.0 on a Retina display.
+	pub fn scale_factor(&self) -> f32 {
+		let window = self.backend.window.handle();
+		dpi::scale_factor(window.size(), window.drawable_size())
+	}
+
+	/// Converts a point in window units, such as a mouse position, into framebuffer pixels.
+	///
+	/// Both have their origin in the top-left corner; glium's is in the bottom-left corner.
+	pub fn to_drawable_point(&self, point: (i32, i32)) -> (i32, i32) {
+		let window = self.backend.window.handle();
+		dpi::scale_point(point, window.size(), window.drawable_size())
+	}
+
+	/// Converts a point in framebuffer pixels into window units.
+	pub fn to_logical_point(&self, point: (i32, i32)) -> (i32, i32) {
+		let window = self.backend.window.handle();
+		dpi::scale_point(point, window.drawable_size(), window.size())
+	}
+
+	/// Converts a rectangle in window units into framebuffer pixels.
+	pub fn to_drawable_rect(&self, rect: Rect) -> Rect {
+		let window = self.backend.window.handle();
+		dpi::scale_rect(rect, window.size(), window.drawable_size())
 	}
 
-	pub fn window_mut(&mut self) -> &mut Window {
-		self.backend.window_mut()
+	/// Converts a rectangle in framebuffer pixels into window units.
+	pub fn to_logical_rect(&self, rect: Rect) -> Rect {
+		let window = self.backend.window.handle();
+		dpi::scale_rect(rect, window.drawable_size(), window.size())
+	}
+
+	/// Updates the facade from an event of the event pump.
+	///
+	/// The framebuffer dimensions are cached, because glium asks for them on every draw call.
+	/// They are refreshed when this gets the `SizeChanged` or `Resized` events of this facade's
+	/// window, and its `DisplayChanged` or `Moved` events, after which the drawable may have
+	/// another scale. Every event must go through here before drawing again. Events of other
+	/// windows are ignored. Debug builds panic when glium gets outdated dimensions.
+	///
+	/// Resizing the window with `resize` or through `window_mut` doesn't need an event.
+	pub fn handle_event(&self, event: &Event) {
+		match *event {
+			Event::Window {
+				window_id,
+				win_event:
+					WindowEvent::SizeChanged(..)
+					| WindowEvent::Resized(..)
+					| WindowEvent::DisplayChanged(_)
+					| WindowEvent::Moved(..),
+				..
+			} if window_id == self.backend.window.handle().id() => {
+				self.backend.framebuffer_dimensions.set(self.backend.window.handle().drawable_size());
+			}
+			_ => {}
+		}
+	}
+
+	/// Returns the new scale factor if it changed since the last call, which happens when the
+	/// window moves to a display with a different scale.
+	///
+	/// Call this with the events of the event pump. Only window events of this facade's window
+	/// are looked at, so events of other windows can be passed as well.
+	pub fn scale_factor_changed(&self, event: &Event) -> Option<f32> {
+		match *event {
+			Event::Window {
+				window_id,
+				win_event: WindowEvent::DisplayChanged(_) | WindowEvent::SizeChanged(..) | WindowEvent::Moved(..),
+				..
+			} if window_id == self.backend.window.handle().id() => {}
+			_ => return None,
+		}
+
+		let scale_factor = self.scale_factor();
+		if scale_factor == self.backend.scale_factor.replace(scale_factor) {
+			return None;
+		}
+
+		Some(scale_factor)
+	}
+
+	/// Resizes the window so that its framebuffer has the given dimensions.
+	///
+	/// The dimensions are in framebuffer pixels, like the ones returned by
+	/// `get_framebuffer_dimensions`, and are converted to window units on HiDPI displays.
+	pub fn resize(&self, new_size: (u32, u32)) {
+		self.backend.resize(new_size)
+	}
+
+	/// Builds another window whose OpenGL context shares its objects with this one.
+	///
+	/// Textures, buffers, programs and the other glium resources created from either facade
+	/// can be used to draw on both of them. Each facade makes its own context current when
+	/// it starts drawing, so windows can be drawn to one after the other.
+	///
+	/// This facade must not have been built with `build_glium_unchecked`, as glium then
+	/// assumes that its context always stays current.
+	pub fn build_shared_window(
+		&self,
+		window_builder: &mut sdl2::video::WindowBuilder,
+	) -> Result<SDL2Facade, GliumSdl2Error> {
+		let backend = Rc::new(self.backend.new_shared(window_builder)?);
+		let context = unsafe { Context::new(backend.clone(), true, Default::default())? };
+
+		Ok(SDL2Facade::new(context, backend))
 	}
 
 	/// Start drawing on the backbuffer.
@@ -148,8 +782,150 @@ impl SDL2Facade {
 	///
 	/// Note that destroying a `Frame` is immediate, even if vsync is enabled.
 	pub fn draw(&self) -> glium::Frame {
+		// another window's context may have been made current since the last frame
+		self.make_current_if_needed();
+		self.backend.resize_offscreen();
+
 		glium::Frame::new(self.context.clone(), self.backend.get_framebuffer_dimensions())
 	}
+
+	/// Start drawing on the backbuffer, recording the times of the frame.
+	///
+	/// This works like `draw`, but the CPU time until `TimedFrame::finish`, the time spent
+	/// swapping the buffers and the GPU time of the frame are added to `frame_stats`. The GPU
+	/// time is measured with a `GL_TIME_ELAPSED` query, whose result is read a few frames later
+	/// so as not to stall. Draw calls of the frame can't use their own `TimeElapsedQuery`.
+	pub fn draw_timed(&self) -> TimedFrame {
+		let frame = self.draw();
+
+		let load = || {
+			let extensions = self.get_extensions();
+			if extensions.gl_ext_disjoint_timer_query {
+				gl::load_timer_queries(self.backend.subsystem(), true)
+			} else if *self.get_opengl_version() >= glium::Version(glium::Api::Gl, 3, 3)
+				|| extensions.gl_arb_timer_query
+			{
+				gl::load_timer_queries(self.backend.subsystem(), false)
+			} else {
+				None
+			}
+		};
+
+		TimedFrame::new(frame, self.backend.clone(), load)
+	}
+
+	/// Start drawing at a fixed resolution, which is scaled to the window when the frame is
+	/// finished.
+	///
+	/// The picture is scaled according to `mode`, from the current `drawable_size`, so it
+	/// follows the size of the window. Its texture is kept from one frame to the next.
+	pub fn draw_virtual(
+		&self,
+		resolution: (u32, u32),
+		mode: ScaleMode,
+	) -> Result<VirtualFrame, GliumSdl2Error> {
+		let viewport = self.virtual_viewport(resolution, mode);
+		let (width, height) = viewport.resolution();
+
+		let cached = self.virtual_texture.borrow_mut().take();
+		let texture = match cached {
+			Some(texture) if texture.dimensions() == (width, height) => texture,
+			_ => screenshot::frame_texture(self, width, height)?,
+		};
+
+		Ok(VirtualFrame::new(self.draw(), texture, self.virtual_texture.clone(), viewport))
+	}
+
+	/// Returns where a virtual resolution is shown in the window with a scale mode, to convert
+	/// mouse coordinates into virtual pixels.
+	pub fn virtual_viewport(&self, resolution: (u32, u32), mode: ScaleMode) -> VirtualViewport {
+		let window = self.backend.window.handle();
+		VirtualViewport::new(resolution, mode, window.size(), window.drawable_size())
+	}
+
+	/// Sets the icon of the window from an image, such as one loaded with the `image` crate and
+	/// `RawImage2d::from_raw_rgba_reversed`.
+	///
+	/// Like every `RawImage2d`, the rows go from the bottom to the top. The image must have
+	/// 8-bit RGB or RGBA pixels.
+	pub fn set_icon_from_image(&self, image: glium::texture::RawImage2d<u8>) -> Result<(), GliumSdl2Error> {
+		let icon = surface::surface_from_raw_image(&image)?;
+		self.window_mut().set_icon(icon);
+
+		Ok(())
+	}
+
+	/// Shows a texture as the mouse cursor, with its hotspot in pixels from the top left.
+	///
+	/// The cursor is shown until the returned handle is dropped.
+	pub fn set_cursor_from_texture(
+		&self,
+		texture: &glium::Texture2d,
+		hotspot: (i32, i32),
+	) -> Result<CursorHandle, GliumSdl2Error> {
+		CursorHandle::new(&[(texture, std::time::Duration::from_secs(0))], hotspot)
+	}
+
+	/// Shows an animated mouse cursor, showing each texture for its duration in turn, with
+	/// their hotspot in pixels from the top left.
+	///
+	/// The animation moves forward in `CursorHandle::update`, and the cursor is shown until the
+	/// handle is dropped.
+	///
+	/// # Panics
+	///
+	/// Panics if there are no frames.
+	pub fn set_animated_cursor(
+		&self,
+		frames: &[(&glium::Texture2d, std::time::Duration)],
+		hotspot: (i32, i32),
+	) -> Result<CursorHandle, GliumSdl2Error> {
+		CursorHandle::new(frames, hotspot)
+	}
+
+	/// Returns the last frame presented on the window, as RGBA with rows from top to bottom.
+	///
+	/// This reads the front buffer, so it must be called after `Frame::finish`. Use
+	/// `screenshot_frame` to read a frame that is still being drawn.
+	pub fn screenshot(&self) -> Result<Screenshot, GliumSdl2Error> {
+		screenshot::read_front_buffer(self)
+	}
+
+	/// Returns what has been drawn on a frame of this facade so far, as RGBA with rows from
+	/// top to bottom.
+	///
+	/// The frame can keep being drawn on afterwards.
+	pub fn screenshot_frame(&self, frame: &glium::Frame) -> Result<Screenshot, GliumSdl2Error> {
+		screenshot::read_frame(self, frame)
+	}
+
+	/// Writes the last frame presented on the window to a file, in the format given by the
+	/// extension of the path, such as PNG.
+	#[cfg(feature = "image")]
+	pub fn save_screenshot<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), GliumSdl2Error> {
+		self.screenshot()?.save(path)
+	}
+
+	/// Creates an `AsyncReadback`, that reads frames of this facade back without stalling and
+	/// hands each one back `delay` frames after it was queued.
+	///
+	/// A delay of 2 or 3 frames is usually enough for the GPU to have finished the copy.
+	/// Returns `SyncNotSupported` if the context doesn't support fences.
+	pub fn async_readback(&self, delay: usize) -> Result<AsyncReadback, GliumSdl2Error> {
+		AsyncReadback::new(self, delay)
+	}
+
+	/// Returns statistics on the times of the last frames drawn with `draw_timed`.
+	pub fn frame_stats(&self) -> FrameStats {
+		self.backend.frame_timer.borrow().stats()
+	}
+
+	/// Makes the context current, if drawing on another facade made its own context current.
+	fn make_current_if_needed(&self) {
+		if !self.backend.is_current() {
+			unsafe { self.backend.make_current() };
+		}
+	}
 }
 
 /// An object that can build a facade object.
@@ -182,6 +958,37 @@ pub trait DisplayBuild {
 	/// are supported by the implementation.
 	fn build_glium_debug(self, callback: debug::DebugCallbackBehavior) -> Result<Self::Facade, Self::Err>;
 
+	/// Build a context and a facade to draw on it, with the given context configuration.
+	///
+	/// The attributes of the configuration are set on SDL's video subsystem for the build.
+	/// Afterwards, the ones that SDL can report back (version, profile, context flags, sRGB,
+	/// sharing) are restored to their previous values, along with the reset notification
+	/// strategy set by `request_robust_context`. SDL can't report the framebuffer ones (depth,
+	/// stencil, multisampling, double buffering), which are put back to SDL's defaults instead:
+	/// 16 depth bits, no stencil buffer, no multisampling and double buffering.
+	/// Attributes that aren't part of the configuration are left untouched.
+	fn build_glium_with(self, config: &GlConfig) -> Result<Self::Facade, Self::Err>;
+
+	/// Build a context and a facade to draw on it, failing unless the context has the
+	/// requested attributes.
+	///
+	/// This does the same as `build_glium_with`, then returns
+	/// `GliumSdl2Error::AttributeMismatch` with the requested and obtained values of every
+	/// attribute for which the driver gave less than requested.
+	fn build_glium_strict(self, config: &GlConfig) -> Result<Self::Facade, Self::Err>;
+
+	/// Build a context and a facade to draw on it, with the first configuration that works.
+	///
+	/// The configurations are tried in order, typically from the most to the least demanding
+	/// version and profile (for example 4.5 core, then 3.3 core, then 2.1 compatibility, then
+	/// ES 2.0). A configuration is skipped if SDL can't create the window or the context, or
+	/// if the context fails glium's compatibility check. The window of a failed attempt is
+	/// destroyed before the next one.
+	///
+	/// Returns the facade along with the index of the configuration that was used, or the
+	/// error of the last attempt if none worked.
+	fn build_glium_fallback(self, configs: &[GlConfig]) -> Result<(Self::Facade, usize), Self::Err>;
+
 	/// Build a context and a facade to draw on it
 	///
 	/// This function does the same as `build_glium`, except that the resulting context
@@ -202,9 +1009,31 @@ pub trait DisplayBuild {
 		callback: debug::DebugCallbackBehavior,
 	) -> Result<Self::Facade, Self::Err>;
 
-	// TODO
-	// Changes the settings of an existing facade.
-	// fn rebuild_glium(self, &Self::Facade) -> Result<(), Self::Err>;
+	/// Changes the settings of an existing facade.
+	///
+	/// Panics if the facade's window is borrowed.
+	///
+	/// The window and its OpenGL context are recreated with the current attributes, so this
+	/// is how MSAA, depth bits or sRGB get changed after creation.
+	/// The new context shares its objects with the old one, so textures, buffers, programs and
+	/// the other glium resources created from the facade remain usable.
+	///
+	/// Only objects that OpenGL never shares between contexts are invalidated: framebuffer and
+	/// vertex array objects, which glium recreates on its own, and queries, which must be
+	/// recreated by the caller.
+	///
+	/// glium keeps using the version and capabilities detected when the facade was first built,
+	/// so the new context must have the same version, profile and forward compatibility, and a
+	/// depth and a stencil buffer only if the old one had them. Otherwise this returns
+	/// `GliumSdl2Error::AttributeMismatch`, with the old values as the requested ones, and the
+	/// facade is left unchanged.
+	fn rebuild_glium(self, facade: &Self::Facade) -> Result<(), Self::Err>;
+
+	/// Changes the settings of an existing facade to the given configuration.
+	///
+	/// This does the same as `rebuild_glium`, but with the attributes of the configuration
+	/// instead of the current ones, which are restored afterwards like in `build_glium_with`.
+	fn rebuild_glium_with(self, config: &GlConfig, facade: &Self::Facade) -> Result<(), Self::Err>;
 }
 
 impl<'a> DisplayBuild for &'a mut sdl2::video::WindowBuilder {
@@ -215,11 +1044,48 @@ impl<'a> DisplayBuild for &'a mut sdl2::video::WindowBuilder {
 		let backend = Rc::new(SDL2WindowBackend::new(self)?);
 		let context = unsafe { Context::new(backend.clone(), true, debug)? };
 
-		let display = SDL2Facade { context: context, backend: backend };
+		Ok(SDL2Facade::new(context, backend))
+	}
+
+	fn build_glium_with(self, config: &GlConfig) -> Result<SDL2Facade, GliumSdl2Error> {
+		if config.high_dpi {
+			self.allow_highdpi();
+		}
+
+		let saved_attributes = config::SavedAttributes::save()?;
+		let display = config.apply().map_err(GliumSdl2Error::from).and_then(|()| self.build_glium());
+		saved_attributes.restore()?;
+
+		display
+	}
+
+	fn build_glium_strict(self, config: &GlConfig) -> Result<SDL2Facade, GliumSdl2Error> {
+		let display = self.build_glium_with(config)?;
+
+		let mismatches = config.mismatches(&display.context_attributes());
+		if !mismatches.is_empty() {
+			return Err(GliumSdl2Error::AttributeMismatch(mismatches));
+		}
 
 		Ok(display)
 	}
 
+	fn build_glium_fallback(self, configs: &[GlConfig]) -> Result<(SDL2Facade, usize), GliumSdl2Error> {
+		let mut last_error = GliumSdl2Error::ContextCreationError("no configuration to try".into());
+
+		for (index, config) in configs.iter().enumerate() {
+			match (&mut *self).build_glium_with(config) {
+				Ok(display) => return Ok((display, index)),
+				Err(err @ GliumSdl2Error::WindowBuildError(WindowBuildError::SdlError(_)))
+				| Err(err @ GliumSdl2Error::ContextCreationError(_))
+				| Err(err @ GliumSdl2Error::IncompatibleOpenGl(_)) => last_error = err,
+				Err(err) => return Err(err),
+			}
+		}
+
+		Err(last_error)
+	}
+
 	unsafe fn build_glium_unchecked_debug(
 		self,
 		debug: debug::DebugCallbackBehavior,
@@ -227,50 +1093,270 @@ impl<'a> DisplayBuild for &'a mut sdl2::video::WindowBuilder {
 		let backend = Rc::new(SDL2WindowBackend::new(self)?);
 		let context = Context::new(backend.clone(), false, debug)?;
 
-		let display = SDL2Facade { context: context, backend: backend };
+		Ok(SDL2Facade::new(context, backend))
+	}
 
-		Ok(display)
+	fn rebuild_glium(self, facade: &SDL2Facade) -> Result<(), GliumSdl2Error> {
+		let new_backend = Rc::new(facade.backend.new_shared(self)?);
+
+		// glium doesn't detect the version and capabilities again when rebuilding, so they are
+		// detected by a context of its own and compared
+		let mismatches = {
+			let context = unsafe { Context::new(new_backend.clone(), true, debug::DebugCallbackBehavior::Ignore)? };
+			config::rebuild_mismatches(&facade.context, &context)
+		};
+		let new_backend = match Rc::try_unwrap(new_backend) {
+			Ok(backend) => backend,
+			Err(_) => unreachable!("glium kept a reference to the backend after its context was dropped"),
+		};
+		if !mismatches.is_empty() {
+			return Err(GliumSdl2Error::AttributeMismatch(mismatches));
+		}
+
+		facade.backend.replace(new_backend);
+		unsafe { facade.context.rebuild(facade.backend.clone())? };
+
+		Ok(())
+	}
+
+	fn rebuild_glium_with(self, config: &GlConfig, facade: &SDL2Facade) -> Result<(), GliumSdl2Error> {
+		if config.high_dpi {
+			self.allow_highdpi();
+		}
+
+		let saved_attributes = config::SavedAttributes::save()?;
+		let result = config.apply().map_err(GliumSdl2Error::from).and_then(|()| self.rebuild_glium(facade));
+		saved_attributes.restore()?;
+
+		result
+	}
+}
+
+/// An object that can build a facade without showing a window.
+pub trait HeadlessBuild {
+	/// Build a context and a facade that draw on a hidden window of the given size.
+	///
+	/// This is meant for rendering in CI or on servers, for example under Mesa's llvmpipe.
+	/// Frames drawn with `draw` can be read back with `read_front_buffer` or
+	/// `SDL2Facade::screenshot` once they are finished.
+	///
+	/// With SDL's `offscreen` video driver, the window is backed by a pbuffer. The other drivers
+	/// leave the pixels of a window that isn't shown undefined, so the facade draws on a
+	/// framebuffer object with an RGBA8 color buffer and a 24 bits depth, 8 bits stencil buffer
+	/// instead, which `draw`, `read_front_buffer` and blits from the frame use in place of the
+	/// default framebuffer. Buffer swaps then do nothing, as that single color buffer is both
+	/// the front and the back buffer.
+	///
+	/// Returns `GliumSdl2Error::VideoInitError`, naming the video driver, if it can't provide
+	/// OpenGL, which is the case of SDL's `dummy` driver, or framebuffer objects.
+	fn build_glium_headless(&self, width: u32, height: u32) -> Result<SDL2Facade, GliumSdl2Error>;
+}
+
+impl HeadlessBuild for VideoSubsystem {
+	fn build_glium_headless(&self, width: u32, height: u32) -> Result<SDL2Facade, GliumSdl2Error> {
+		// Creating the window would fail too, but with a message that doesn't say why.
+		if let Err(err) = self.gl_load_library_default() {
+			return Err(GliumSdl2Error::VideoInitError(format!(
+				"the \"{}\" video driver can't provide OpenGL: {}",
+				self.current_video_driver(),
+				err
+			)));
+		}
+
+		let mut window_builder = self.window("glium_sdl2", width, height);
+		window_builder.hidden().borderless();
+		let display = if self.current_video_driver() == "offscreen" {
+			window_builder.build_glium()
+		} else {
+			SDL2WindowBackend::new(&mut window_builder).and_then(|backend| {
+				backend.draw_offscreen()?;
+				let backend = Rc::new(backend);
+				let context = unsafe { Context::new(backend.clone(), true, Default::default())? };
+				Ok(SDL2Facade::new(context, backend))
+			})
+		};
+
+		// the window holds its own reference to the library
+		self.gl_unload_library();
+
+		display
 	}
 }
 
 pub struct SDL2WindowBackend {
-	window: UnsafeCell<Window>,
-	context: sdl2::video::GLContext,
+	// the context is declared first so that it's destroyed before its window
+	context: RefCell<sdl2::video::GLContext>,
+	window: window::SharedWindow<Window>,
+	subsystem: VideoSubsystem,
+	make_current_policy: Cell<MakeCurrentPolicy>,
+	skip_next_swap: Cell<bool>,
+
+	// only loaded if the context was created with the "lose context on reset" strategy
+	get_reset_status: Cell<Option<gl::GetGraphicsResetStatusFn>>,
+
+	// queried at creation, while the default framebuffer is still bound
+	framebuffer_attributes: Cell<config::FramebufferAttributes>,
+
+	// the last scale factor reported by `SDL2Facade::scale_factor_changed`
+	scale_factor: Cell<f32>,
+
+	// kept up to date by `SDL2Facade::handle_event`, as glium asks for them all the time
+	framebuffer_dimensions: Cell<(u32, u32)>,
+
+	// drawn on instead of the default framebuffer by headless facades (see `HeadlessBuild`)
+	headless: RefCell<Option<headless::HeadlessFramebuffer>>,
+
+	frame_timer: RefCell<timing::FrameTimer>,
 }
 
 impl SDL2WindowBackend {
 	fn subsystem(&self) -> &VideoSubsystem {
-		let ptr = self.window.get();
-		let window = unsafe { &*ptr };
-		window.subsystem()
+		&self.subsystem
 	}
 
-	fn window(&self) -> &Window {
-		let ptr = self.window.get();
-		let window = unsafe { &*ptr };
-		window
+	pub fn new(window_builder: &mut sdl2::video::WindowBuilder) -> Result<SDL2WindowBackend, GliumSdl2Error> {
+		let window = window_builder.opengl().build()?;
+		SDL2WindowBackend::from_window(window)
 	}
 
-	fn window_mut(&self) -> &mut Window {
-		let ptr = self.window.get();
-		let window = unsafe { &mut *ptr };
-		window
+	/// Creates an OpenGL context for a window that was built with the `opengl` flag.
+	pub fn from_window(window: Window) -> Result<SDL2WindowBackend, GliumSdl2Error> {
+		let context = window.gl_create_context()?;
+		unsafe { SDL2WindowBackend::from_window_and_context(window, context) }
 	}
 
-	pub fn new(window_builder: &mut sdl2::video::WindowBuilder) -> Result<SDL2WindowBackend, GliumSdl2Error> {
-		let window = window_builder.opengl().build()?;
-		let context = window.gl_create_context()?;
+	/// Uses an existing OpenGL context for a window.
+	///
+	/// # Safety
+	///
+	/// The context must have been created for this window, or for one with the same pixel
+	/// format, and must not be used by anything else while the backend is alive.
+	pub unsafe fn from_window_and_context(
+		window: Window,
+		context: sdl2::video::GLContext,
+	) -> Result<SDL2WindowBackend, GliumSdl2Error> {
+		// the context must be current to be queried
+		window.gl_make_current(&context).map_err(GliumSdl2Error::MakeCurrentFailed)?;
+
+		let get_reset_status = gl::load_reset_status(window.subsystem());
+		let framebuffer_attributes = config::FramebufferAttributes::query();
+		let scale_factor = dpi::scale_factor(window.size(), window.drawable_size());
+		let framebuffer_dimensions = window.drawable_size();
+
+		Ok(SDL2WindowBackend {
+			context: RefCell::new(context),
+			subsystem: window.subsystem().clone(),
+			window: window::SharedWindow::new(window),
+			make_current_policy: Cell::new(MakeCurrentPolicy::default()),
+			skip_next_swap: Cell::new(false),
+			get_reset_status: Cell::new(get_reset_status),
+			framebuffer_attributes: Cell::new(framebuffer_attributes),
+			scale_factor: Cell::new(scale_factor),
+			framebuffer_dimensions: Cell::new(framebuffer_dimensions),
+			headless: RefCell::new(None),
+			frame_timer: RefCell::new(timing::FrameTimer::new()),
+		})
+	}
+
+	/// Builds a window whose context shares its objects with this backend's context.
+	fn new_shared(
+		&self,
+		window_builder: &mut sdl2::video::WindowBuilder,
+	) -> Result<SDL2WindowBackend, GliumSdl2Error> {
+		// the new context shares objects with whichever context is current while it's created
+		self.try_make_current()?;
+
+		let gl_attr = self.subsystem().gl_attr();
+		let share_with_current_context = gl_attr.share_with_current_context();
+		gl_attr.set_share_with_current_context(true);
+		let backend = SDL2WindowBackend::new(window_builder);
+		gl_attr.set_share_with_current_context(share_with_current_context);
 
-		Ok(SDL2WindowBackend { window: UnsafeCell::new(window), context: context })
+		backend
 	}
+
+	/// Takes over the window and context of `other`, destroying the current ones.
+	///
+	/// Panics if the window is borrowed.
+	fn replace(&self, other: SDL2WindowBackend) {
+		let SDL2WindowBackend { context, window, headless, .. } = other;
+		let old_window = self.window.replace(window.into_inner());
+		// the context goes first, as it was created for the old window
+		drop(self.context.replace(context.into_inner()));
+		drop(old_window);
+
+		self.skip_next_swap.set(false);
+		self.get_reset_status.set(other.get_reset_status.get());
+		self.framebuffer_attributes.set(other.framebuffer_attributes.get());
+		self.scale_factor.set(other.scale_factor.get());
+		self.framebuffer_dimensions.set(other.framebuffer_dimensions.get());
+		self.headless.replace(headless.into_inner());
+		// the queries belonged to the old context
+		self.frame_timer.replace(timing::FrameTimer::new());
+	}
+
+	fn try_make_current(&self) -> Result<(), GliumSdl2Error> {
+		self.window.handle().gl_make_current(&self.context.borrow()).map_err(GliumSdl2Error::MakeCurrentFailed)
+	}
+
+	/// Draws on a framebuffer object instead of the default framebuffer, which hidden windows
+	/// don't have with most video drivers.
+	///
+	/// Must be called before glium is started on the context.
+	fn draw_offscreen(&self) -> Result<(), GliumSdl2Error> {
+		self.try_make_current()?;
+		let framebuffer = headless::HeadlessFramebuffer::new(&self.window.handle()).map_err(|err| {
+			GliumSdl2Error::VideoInitError(format!(
+				"the \"{}\" video driver can't draw on hidden windows: {}",
+				self.subsystem().current_video_driver(),
+				err
+			))
+		})?;
+
+		self.framebuffer_attributes.set(headless::HeadlessFramebuffer::attributes());
+		self.headless.replace(Some(framebuffer));
+		Ok(())
+	}
+
+	/// Gives the framebuffer object of a headless facade the current framebuffer dimensions.
+	fn resize_offscreen(&self) {
+		if let Some(ref framebuffer) = *self.headless.borrow() {
+			if self.is_current() {
+				framebuffer.resize(self.get_framebuffer_dimensions());
+			}
+		}
+	}
+}
+
+/// Converts a length in drawable pixels into window units, given the current size in both units.
+fn drawable_to_window_units(length: u32, window_length: u32, drawable_length: u32) -> u32 {
+	if window_length == 0 || drawable_length == 0 {
+		return length;
+	}
+
+	let (length, window_length, drawable_length) =
+		(u64::from(length), u64::from(window_length), u64::from(drawable_length));
+	((length * window_length + drawable_length / 2) / drawable_length) as u32
 }
 
 unsafe impl Backend for SDL2WindowBackend {
 	fn swap_buffers(&self) -> Result<(), SwapBuffersError> {
-		self.window().gl_swap_window();
+		if self.skip_next_swap.replace(false) {
+			return Ok(());
+		}
+
+		// the frame stays in the framebuffer object of headless facades
+		if self.headless.borrow().is_none() {
+			self.window.handle().gl_swap_window();
+		}
 
-		// AFAIK, SDL or `SDL_GL_SwapWindow` doesn't have any way to detect context loss.
-		// TODO: Find out if context loss is an issue in SDL2 (especially for the Android port).
+		// SDL doesn't report context loss by itself, but robust contexts can be asked whether
+		// a reset happened (see `request_robust_context`).
+		if let Some(get_reset_status) = self.get_reset_status.get() {
+			if get_reset_status() != gl::NO_ERROR {
+				return Err(SwapBuffersError::ContextLost);
+			}
+		}
 
 		Ok(())
 	}
@@ -278,23 +1364,66 @@ unsafe impl Backend for SDL2WindowBackend {
 	unsafe fn get_proc_address(&self, symbol: &str) -> *const c_void {
 		// Assumes the appropriate context for the window has been set before this call.
 
+		if self.headless.borrow().is_some() {
+			if let Some(entry_point) = headless::redirected_entry_point(symbol) {
+				return entry_point;
+			}
+		}
+
 		self.subsystem().gl_get_proc_address(symbol) as *const c_void
 	}
 
 	fn get_framebuffer_dimensions(&self) -> (u32, u32) {
-		let (width, height) = self.window().drawable_size();
-		(width as u32, height as u32)
+		// the window can be resized at any time through a guard returned by `window_mut`
+		if self.window.lent().try_borrow().is_err() {
+			self.framebuffer_dimensions.set(self.window.handle().drawable_size());
+		}
+
+		let dimensions = self.framebuffer_dimensions.get();
+		debug_assert_eq!(
+			dimensions,
+			self.window.handle().drawable_size(),
+			"the window was resized without its events going through `SDL2Facade::handle_event`"
+		);
+		dimensions
 	}
 
-	fn resize(&self, _new_size: (u32, u32)) {
-		unimplemented!()
+	fn resize(&self, new_size: (u32, u32)) {
+		// glium speaks in framebuffer pixels, whereas SDL sizes windows in screen coordinates.
+		// The two only differ on HiDPI displays, where the drawable is scaled up from the window.
+		let window = self.window.handle();
+		let (width, height) = window.size();
+		let (drawable_width, drawable_height) = window.drawable_size();
+
+		let width = drawable_to_window_units(new_size.0, width, drawable_width);
+		let height = drawable_to_window_units(new_size.1, height, drawable_height);
+
+		// Make sure that no dimension is zero, which SDL refuses, and that it doesn't overflow.
+		// SDL's event watchers may run during the call and query the backend, so the window
+		// is only borrowed as shared, which `Window::set_size` doesn't allow.
+		let clamp = |length: u32| length.clamp(1, i32::MAX as u32) as i32;
+		unsafe { sdl2::sys::SDL_SetWindowSize(window.raw(), clamp(width), clamp(height)) };
+		self.framebuffer_dimensions.set(window.drawable_size());
 	}
 
 	fn is_current(&self) -> bool {
-		self.context.is_current()
+		self.context.borrow().is_current()
 	}
 
 	unsafe fn make_current(&self) {
-		self.window().gl_make_current(&self.context).unwrap()
+		if let Err(err) = self.try_make_current() {
+			match self.make_current_policy.get() {
+				MakeCurrentPolicy::Panic => panic!("{}", err),
+				MakeCurrentPolicy::LogAndSkipFrame => {
+					// glium would otherwise create and cache its objects in another facade's context
+					let _ = self.subsystem().gl_release_current_context();
+
+					// glium calls this before every batch of commands until the context is current
+					if !self.skip_next_swap.replace(true) {
+						eprintln!("glium_sdl2: {}; skipping frame", err);
+					}
+				}
+			}
+		}
 	}
 }
@@ -0,0 +1,193 @@
+//! Reading frames back without stalling, for `SDL2Facade::async_readback`.
+
+use std::collections::VecDeque;
+
+use glium::texture::pixel_buffer::PixelBuffer;
+use glium::texture::{RawImage2d, Texture2d};
+use glium::{CapabilitiesSource, Frame, Surface};
+
+use gl;
+use screenshot::{self, Screenshot};
+use GliumSdl2Error;
+use SDL2Facade;
+
+/// How long a blocking wait on a fence lasts before checking it again, in nanoseconds.
+const WAIT_SLICE: u64 = 100_000_000;
+
+/// A frame read back by an `AsyncReadback`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReadbackFrame {
+	/// The number of the frame, counting the frames queued since the readback was created.
+	pub index: u64,
+
+	pub image: Screenshot,
+}
+
+/// A read that the GPU may not have done yet.
+struct PendingRead {
+	index: u64,
+	texture: Texture2d,
+	buffer: PixelBuffer<(u8, u8, u8, u8)>,
+	fence: gl::GLsync,
+}
+
+/// Reads frames back into pixel buffers, and hands them back a few frames later, once the GPU
+/// has copied them.
+///
+/// Reading a frame with `SDL2Facade::screenshot_frame` makes the CPU wait for the GPU to finish
+/// drawing it, which stalls the pipeline. An `AsyncReadback` only queues the copy, and checks
+/// with a fence whether it is done, so that frames can be captured continuously.
+///
+/// ```no_run
+/// # extern crate glium;
+/// # extern crate glium_sdl2;
+/// # fn main() {
+/// # let display: glium_sdl2::SDL2Facade = unimplemented!();
+/// use glium::Surface;
+///
+/// let mut readback = display.async_readback(2).unwrap();
+///
+/// loop {
+///     let mut target = display.draw();
+///     target.clear_color(0.0, 0.0, 0.0, 1.0);
+///
+///     // the frame drawn two frames ago
+///     if let Some(frame) = readback.queue(&target).unwrap() {
+///         // write frame.image somewhere
+///     }
+///     target.finish().unwrap();
+/// }
+/// # }
+/// ```
+pub struct AsyncReadback {
+	facade: SDL2Facade,
+	fences: gl::Fences,
+	delay: usize,
+	next_index: u64,
+	pending: VecDeque<PendingRead>,
+
+	// the textures of the reads that were handed back, to copy the next frames to
+	free: Vec<Texture2d>,
+}
+
+impl AsyncReadback {
+	pub(crate) fn new(facade: &SDL2Facade, delay: usize) -> Result<AsyncReadback, GliumSdl2Error> {
+		let version = *facade.get_opengl_version();
+		let supported = version >= glium::Version(glium::Api::Gl, 3, 2)
+			|| version >= glium::Version(glium::Api::GlEs, 3, 0)
+			|| facade.get_extensions().gl_arb_sync;
+
+		facade.make_current_if_needed();
+		let fences = match gl::load_fences(facade.backend.subsystem()) {
+			Some(fences) if supported => fences,
+			_ => return Err(GliumSdl2Error::SyncNotSupported),
+		};
+
+		Ok(AsyncReadback {
+			facade: facade.clone(),
+			fences,
+			delay,
+			next_index: 0,
+			pending: VecDeque::with_capacity(delay + 1),
+			free: Vec::new(),
+		})
+	}
+
+	/// The number of frames between queuing a frame and getting it back from `queue`.
+	pub fn delay(&self) -> usize {
+		self.delay
+	}
+
+	/// The number of frames queued and not handed back yet.
+	pub fn in_flight(&self) -> usize {
+		self.pending.len()
+	}
+
+	/// Queues a read of what has been drawn on a frame so far, and returns the frame queued
+	/// `delay` frames ago, if any.
+	///
+	/// This is meant to be called right before `Frame::finish`. If the GPU is more than `delay`
+	/// frames behind, this waits for it to copy the returned frame.
+	pub fn queue(&mut self, frame: &Frame) -> Result<Option<ReadbackFrame>, GliumSdl2Error> {
+		let (width, height) = frame.get_dimensions();
+
+		// the window may have been resized since the textures were created
+		self.free.retain(|texture| texture.dimensions() == (width, height));
+		let texture = match self.free.pop() {
+			Some(texture) => texture,
+			None => screenshot::frame_texture(&self.facade, width, height)?,
+		};
+
+		screenshot::copy_frame(frame, &texture);
+		let buffer = texture.read_to_pixel_buffer();
+
+		self.facade.make_current_if_needed();
+		let fence = (self.fences.fence_sync)(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+
+		self.pending.push_back(PendingRead { index: self.next_index, texture, buffer, fence });
+		self.next_index += 1;
+
+		if self.pending.len() > self.delay {
+			self.next(true)
+		} else {
+			Ok(None)
+		}
+	}
+
+	/// Returns the oldest frame in flight if the GPU has finished copying it, without waiting.
+	///
+	/// This lets frames be handed back sooner than `delay` frames later.
+	pub fn try_next(&mut self) -> Result<Option<ReadbackFrame>, GliumSdl2Error> {
+		self.next(false)
+	}
+
+	/// Waits for all the frames in flight, and returns them from the oldest.
+	pub fn drain(&mut self) -> Result<Vec<ReadbackFrame>, GliumSdl2Error> {
+		let mut frames = Vec::with_capacity(self.pending.len());
+		while let Some(frame) = self.next(true)? {
+			frames.push(frame);
+		}
+
+		Ok(frames)
+	}
+
+	/// Returns the oldest frame in flight, if it's ready or `wait` is set.
+	fn next(&mut self, wait: bool) -> Result<Option<ReadbackFrame>, GliumSdl2Error> {
+		let fence = match self.pending.front() {
+			Some(read) => read.fence,
+			None => return Ok(None),
+		};
+
+		self.facade.make_current_if_needed();
+		let client_wait_sync = self.fences.client_wait_sync;
+		let mut status = client_wait_sync(fence, gl::SYNC_FLUSH_COMMANDS_BIT, 0);
+		while wait && status == gl::TIMEOUT_EXPIRED {
+			status = client_wait_sync(fence, gl::SYNC_FLUSH_COMMANDS_BIT, WAIT_SLICE);
+		}
+
+		// a failed wait leaves nothing to wait for, and the read below reports any real error
+		if status == gl::TIMEOUT_EXPIRED {
+			return Ok(None);
+		}
+
+		let read = self.pending.pop_front().unwrap();
+		(self.fences.delete_sync)(read.fence);
+
+		let image: RawImage2d<u8> = read.buffer.read_as_texture_2d()?;
+		self.free.push(read.texture);
+
+		Ok(Some(ReadbackFrame {
+			index: read.index,
+			image: Screenshot::from_bottom_up(&image.data, image.width, image.height),
+		}))
+	}
+}
+
+impl Drop for AsyncReadback {
+	fn drop(&mut self) {
+		self.facade.make_current_if_needed();
+		for read in self.pending.drain(..) {
+			(self.fences.delete_sync)(read.fence);
+		}
+	}
+}
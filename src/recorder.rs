@@ -0,0 +1,340 @@
+//! Recording the frames of a facade to a video stream or a sequence of images.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+#[cfg(feature = "image")]
+use std::path::PathBuf;
+use std::time::Duration;
+
+use glium::index::{NoIndices, PrimitiveType};
+use glium::texture::{RawImage2d, Texture2d};
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerWrapFunction};
+use glium::{Api, Frame, Program, Surface, VertexBuffer};
+
+use screenshot;
+#[cfg(feature = "image")]
+use screenshot::Screenshot;
+use GliumSdl2Error;
+use SDL2Facade;
+
+#[derive(Copy, Clone)]
+struct Vertex {
+	position: [f32; 2],
+}
+
+glium::implement_vertex!(Vertex, position);
+
+const VERTEX_SHADER_140: &str = "
+	#version 140
+
+	in vec2 position;
+	out vec2 v_tex_coords;
+
+	void main() {
+		gl_Position = vec4(position, 0.0, 1.0);
+		// upside down, for the rows to be read back from the top
+		v_tex_coords = vec2(position.x, -position.y) * 0.5 + 0.5;
+	}
+";
+
+const FRAGMENT_SHADER_140: &str = "
+	#version 140
+
+	uniform sampler2D source;
+	in vec2 v_tex_coords;
+	out vec4 color;
+
+	void main() {
+		vec3 rgb = texture(source, v_tex_coords).rgb;
+		color = vec4(
+			dot(rgb, vec3(0.299, 0.587, 0.114)),
+			dot(rgb, vec3(-0.168736, -0.331264, 0.5)) + 0.5,
+			dot(rgb, vec3(0.5, -0.418688, -0.081312)) + 0.5,
+			1.0
+		);
+	}
+";
+
+const VERTEX_SHADER_110: &str = "
+	attribute vec2 position;
+	varying vec2 v_tex_coords;
+
+	void main() {
+		gl_Position = vec4(position, 0.0, 1.0);
+		v_tex_coords = vec2(position.x, -position.y) * 0.5 + 0.5;
+	}
+";
+
+const FRAGMENT_SHADER_110: &str = "
+	uniform sampler2D source;
+	varying vec2 v_tex_coords;
+
+	void main() {
+		vec3 rgb = texture2D(source, v_tex_coords).rgb;
+		gl_FragColor = vec4(
+			dot(rgb, vec3(0.299, 0.587, 0.114)),
+			dot(rgb, vec3(-0.168736, -0.331264, 0.5)) + 0.5,
+			dot(rgb, vec3(0.5, -0.418688, -0.081312)) + 0.5,
+			1.0
+		);
+	}
+";
+
+/// Converts frames from RGB to the planes of Y'CbCr 4:2:0, with the full-range BT.601
+/// coefficients of JPEG.
+///
+/// The luma plane is drawn at the size of the frame. The chroma planes are drawn at half its
+/// size, where linear filtering averages each block of 2x2 pixels.
+struct YuvConverter {
+	program: Program,
+	quad: VertexBuffer<Vertex>,
+	luma: Texture2d,
+	chroma: Texture2d,
+}
+
+impl YuvConverter {
+	fn new(facade: &SDL2Facade, width: u32, height: u32) -> Result<YuvConverter, GliumSdl2Error> {
+		let glsl = facade.get_supported_glsl_version();
+		let (vertex_shader, fragment_shader) = match glsl {
+			glium::Version(Api::GlEs, _, _) => (
+				format!("#version 100\n{}", VERTEX_SHADER_110),
+				format!("#version 100\nprecision mediump float;\n{}", FRAGMENT_SHADER_110),
+			),
+			_ if glsl >= glium::Version(Api::Gl, 1, 40) => {
+				(VERTEX_SHADER_140.to_owned(), FRAGMENT_SHADER_140.to_owned())
+			}
+			_ => (format!("#version 110\n{}", VERTEX_SHADER_110), format!("#version 110\n{}", FRAGMENT_SHADER_110)),
+		};
+
+		let quad = [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]];
+		let quad: Vec<Vertex> = quad.iter().map(|&position| Vertex { position }).collect();
+
+		Ok(YuvConverter {
+			program: Program::from_source(facade, &vertex_shader, &fragment_shader, None)?,
+			quad: VertexBuffer::new(facade, &quad)?,
+			luma: screenshot::frame_texture(facade, width, height)?,
+			chroma: screenshot::frame_texture(facade, width.div_ceil(2), height.div_ceil(2))?,
+		})
+	}
+
+	/// Returns the Y, Cb and Cr planes of an image, with rows from the top.
+	fn convert(&self, source: &Texture2d) -> Result<[Vec<u8>; 3], GliumSdl2Error> {
+		let sampler = source
+			.sampled()
+			.magnify_filter(MagnifySamplerFilter::Linear)
+			.minify_filter(MinifySamplerFilter::Linear)
+			.wrap_function(SamplerWrapFunction::Clamp);
+		let uniforms = glium::uniforms::UniformsStorage::new("source", sampler);
+		let indices = NoIndices(PrimitiveType::TriangleStrip);
+
+		for target in &[&self.luma, &self.chroma] {
+			let parameters = Default::default();
+			target.as_surface().draw(&self.quad, indices, &self.program, &uniforms, &parameters)?;
+		}
+
+		// each plane is in one channel of the RGBA textures
+		let luma: RawImage2d<u8> = self.luma.read();
+		let chroma: RawImage2d<u8> = self.chroma.read();
+		let channel = |data: &[u8], channel: usize| data.chunks(4).map(|pixel| pixel[channel]).collect();
+
+		Ok([channel(&luma.data, 0), channel(&chroma.data, 1), channel(&chroma.data, 2)])
+	}
+}
+
+enum Output {
+	Y4m {
+		writer: Box<dyn Write>,
+		converter: Option<Box<YuvConverter>>,
+	},
+	#[cfg(feature = "image")]
+	Png {
+		directory: PathBuf,
+	},
+}
+
+/// Writes the frames of a facade to an uncompressed Y4M video stream, or to a numbered
+/// sequence of PNG images.
+///
+/// Frames are recorded at a fixed virtual frame rate, rather than at the rate they are drawn:
+/// each recorded frame lasts `1 / frame_rate` seconds of video, however long it took to draw.
+/// To make a recording deterministic, advance the simulation by `timestep` on every frame
+/// instead of by the time that actually passed.
+///
+/// All the frames are recorded at the size of the first one; frames of another size are
+/// scaled to it.
+///
+/// ```no_run
+/// # extern crate glium;
+/// # extern crate glium_sdl2;
+/// # fn main() {
+/// # let display: glium_sdl2::SDL2Facade = unimplemented!();
+/// use glium::Surface;
+/// use glium_sdl2::FrameRecorder;
+///
+/// let mut recorder = FrameRecorder::create_y4m(&display, "capture.y4m", 60).unwrap();
+///
+/// for _ in 0..600 {
+///     let mut target = display.draw();
+///     target.clear_color(0.0, 0.0, 0.0, 1.0);
+///     recorder.record(&target).unwrap();
+///     target.finish().unwrap();
+/// }
+///
+/// recorder.finish().unwrap();
+/// # }
+/// ```
+pub struct FrameRecorder {
+	facade: SDL2Facade,
+	output: Output,
+	frame_rate: u32,
+	every: u32,
+	frames_seen: u64,
+	frames_written: u64,
+
+	// the copy of the frame being recorded, created at the size of the first frame
+	copy: Option<Texture2d>,
+}
+
+impl FrameRecorder {
+	fn new(facade: &SDL2Facade, output: Output, frame_rate: u32) -> FrameRecorder {
+		assert!(frame_rate > 0, "the frame rate must be positive");
+
+		FrameRecorder {
+			facade: facade.clone(),
+			output,
+			frame_rate,
+			every: 1,
+			frames_seen: 0,
+			frames_written: 0,
+			copy: None,
+		}
+	}
+
+	/// Records frames to a Y4M stream, such as the standard input of an encoder.
+	///
+	/// The frames are converted to Y'CbCr 4:2:0 on the GPU, with the BT.601 coefficients and
+	/// chroma siting of JPEG (`C420jpeg`). Values use the full range of 0 to 255, which the
+	/// header declares with `XCOLORRANGE=FULL`, as players assume the limited range of video
+	/// otherwise. The header is written along with the first frame.
+	pub fn y4m<W: Write + 'static>(facade: &SDL2Facade, writer: W, frame_rate: u32) -> FrameRecorder {
+		let output = Output::Y4m { writer: Box::new(writer), converter: None };
+		FrameRecorder::new(facade, output, frame_rate)
+	}
+
+	/// Records frames to a Y4M file, created or truncated.
+	pub fn create_y4m<P: AsRef<Path>>(
+		facade: &SDL2Facade,
+		path: P,
+		frame_rate: u32,
+	) -> Result<FrameRecorder, GliumSdl2Error> {
+		let file = File::create(path)?;
+		Ok(FrameRecorder::y4m(facade, BufWriter::new(file), frame_rate))
+	}
+
+	/// Records frames to PNG images in a directory, created if needed, named after the number
+	/// of the frame in the recording: `000000.png`, `000001.png`...
+	#[cfg(feature = "image")]
+	pub fn png_sequence<P: Into<PathBuf>>(
+		facade: &SDL2Facade,
+		directory: P,
+		frame_rate: u32,
+	) -> Result<FrameRecorder, GliumSdl2Error> {
+		let directory = directory.into();
+		std::fs::create_dir_all(&directory)?;
+
+		Ok(FrameRecorder::new(facade, Output::Png { directory }, frame_rate))
+	}
+
+	/// Records only one frame out of `every`, starting with the next one. The default is 1, to
+	/// record every frame.
+	///
+	/// # Panics
+	///
+	/// Panics if `every` is 0.
+	pub fn set_every(&mut self, every: u32) {
+		assert!(every > 0, "can't record one frame out of 0");
+		self.every = every;
+		self.frames_seen = 0;
+	}
+
+	/// The number of frames recorded per second of video.
+	pub fn frame_rate(&self) -> u32 {
+		self.frame_rate
+	}
+
+	/// The virtual time between two frames passed to `record`: the duration of a frame of
+	/// video, divided by the number of frames it stands for.
+	pub fn timestep(&self) -> Duration {
+		Duration::from_secs(1) / (self.frame_rate * self.every)
+	}
+
+	/// The number of frames recorded so far.
+	pub fn frames_written(&self) -> u64 {
+		self.frames_written
+	}
+
+	/// The duration of the video recorded so far.
+	pub fn duration(&self) -> Duration {
+		Duration::from_secs(self.frames_written) / self.frame_rate
+	}
+
+	/// Records what has been drawn on a frame, unless it is one of the frames skipped by
+	/// `set_every`. Returns whether the frame was recorded.
+	///
+	/// This is meant to be called right before `Frame::finish`.
+	pub fn record(&mut self, frame: &Frame) -> Result<bool, GliumSdl2Error> {
+		let skip = !self.frames_seen.is_multiple_of(u64::from(self.every));
+		self.frames_seen += 1;
+		if skip {
+			return Ok(false);
+		}
+
+		if self.copy.is_none() {
+			let (width, height) = frame.get_dimensions();
+			self.copy = Some(screenshot::frame_texture(&self.facade, width, height)?);
+		}
+		let copy = self.copy.as_ref().unwrap();
+		screenshot::copy_frame(frame, copy);
+
+		match self.output {
+			Output::Y4m { ref mut writer, ref mut converter } => {
+				let (width, height) = copy.dimensions();
+				if converter.is_none() {
+					writeln!(
+						writer,
+						"YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C420jpeg XCOLORRANGE=FULL",
+						width, height, self.frame_rate
+					)?;
+					*converter = Some(Box::new(YuvConverter::new(&self.facade, width, height)?));
+				}
+
+				let planes = converter.as_ref().unwrap().convert(copy)?;
+				writer.write_all(b"FRAME\n")?;
+				for plane in &planes {
+					writer.write_all(plane)?;
+				}
+			}
+			#[cfg(feature = "image")]
+			Output::Png { ref directory } => {
+				let image: RawImage2d<u8> = copy.read();
+				let screenshot = Screenshot::from_bottom_up(&image.data, image.width, image.height);
+				screenshot.save(directory.join(format!("{:06}.png", self.frames_written)))?;
+			}
+		}
+
+		self.frames_written += 1;
+		Ok(true)
+	}
+
+	/// Stops recording, and flushes what remains to be written.
+	pub fn finish(mut self) -> Result<(), GliumSdl2Error> {
+		match self.output {
+			Output::Y4m { ref mut writer, .. } => writer.flush()?,
+			#[cfg(feature = "image")]
+			Output::Png { .. } => (),
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,106 @@
+//! Reading back what a facade draws, as images in the usual top-down row order.
+
+use glium::texture::{MipmapsOption, RawImage2d, Texture2d, UncompressedFloatFormat};
+use glium::uniforms::MagnifySamplerFilter;
+use glium::{BlitTarget, Frame, Surface};
+
+use GliumSdl2Error;
+use SDL2Facade;
+
+/// An RGBA image with 8 bits per channel, whose rows go from top to bottom.
+///
+/// OpenGL returns rows from bottom to top; they are flipped when the image is read back.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Screenshot {
+	pub width: u32,
+	pub height: u32,
+
+	/// The pixels, row after row from the top, 4 bytes per pixel.
+	pub data: Vec<u8>,
+}
+
+impl Screenshot {
+	/// Builds a screenshot from rows in OpenGL order, from bottom to top.
+	pub(crate) fn from_bottom_up(data: &[u8], width: u32, height: u32) -> Screenshot {
+		let row_length = width as usize * 4;
+		if row_length == 0 {
+			return Screenshot { width, height, data: Vec::new() };
+		}
+
+		let data = data.chunks(row_length).rev().flat_map(|row| row.iter().cloned()).collect();
+
+		Screenshot { width, height, data }
+	}
+
+	/// Converts the screenshot into an `image` buffer.
+	#[cfg(feature = "image")]
+	pub fn into_image(self) -> image::RgbaImage {
+		image::RgbaImage::from_raw(self.width, self.height, self.data)
+			.expect("the screenshot doesn't have 4 bytes per pixel")
+	}
+
+	/// Writes the screenshot to a file, in the format given by the extension of the path,
+	/// such as PNG.
+	#[cfg(feature = "image")]
+	pub fn save<P: AsRef<std::path::Path>>(self, path: P) -> Result<(), GliumSdl2Error> {
+		self.into_image().save(path).map_err(GliumSdl2Error::ImageError)
+	}
+}
+
+/// Reads the front buffer of a facade, which holds the last frame that was presented.
+pub(crate) fn read_front_buffer(facade: &SDL2Facade) -> Result<Screenshot, GliumSdl2Error> {
+	let image: RawImage2d<u8> = facade.read_front_buffer()?;
+	Ok(Screenshot::from_bottom_up(&image.data, image.width, image.height))
+}
+
+/// Creates a texture that frames of the given size can be copied to.
+pub(crate) fn frame_texture(
+	facade: &SDL2Facade,
+	width: u32,
+	height: u32,
+) -> Result<Texture2d, GliumSdl2Error> {
+	let texture = Texture2d::empty_with_format(
+		facade,
+		UncompressedFloatFormat::U8U8U8U8,
+		MipmapsOption::NoMipmap,
+		width,
+		height,
+	)?;
+
+	Ok(texture)
+}
+
+/// Copies what has been drawn on a frame so far to a texture, scaling it if their sizes differ.
+pub(crate) fn copy_frame(frame: &Frame, texture: &Texture2d) {
+	let (width, height) = texture.dimensions();
+	let target = BlitTarget { left: 0, bottom: 0, width: width as i32, height: height as i32 };
+	frame.blit_whole_color_to(&texture.as_surface(), &target, MagnifySamplerFilter::Nearest);
+}
+
+/// Reads what has been drawn on a frame so far, by copying it to a texture.
+pub(crate) fn read_frame(facade: &SDL2Facade, frame: &Frame) -> Result<Screenshot, GliumSdl2Error> {
+	let (width, height) = frame.get_dimensions();
+	let texture = frame_texture(facade, width, height)?;
+	copy_frame(frame, &texture);
+
+	let image: RawImage2d<u8> = texture.read();
+	Ok(Screenshot::from_bottom_up(&image.data, image.width, image.height))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Screenshot;
+
+	#[test]
+	fn rows_are_flipped() {
+		let data = [1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4];
+		let screenshot = Screenshot::from_bottom_up(&data, 2, 2);
+		assert_eq!(screenshot.data, [3, 3, 3, 3, 4, 4, 4, 4, 1, 1, 1, 1, 2, 2, 2, 2]);
+	}
+
+	#[test]
+	fn empty_images_have_no_pixels() {
+		assert!(Screenshot::from_bottom_up(&[], 0, 16).data.is_empty());
+		assert!(Screenshot::from_bottom_up(&[], 16, 0).data.is_empty());
+	}
+}
@@ -0,0 +1,217 @@
+//! Conversions between SDL surfaces and glium textures.
+
+use std::slice;
+
+use glium::texture::{ClientFormat, RawImage2d, Texture2d};
+use sdl2::pixels::{PixelFormatEnum, PixelMasks};
+use sdl2::surface::{Surface, SurfaceRef};
+
+use GliumSdl2Error;
+use SDL2Facade;
+
+/// Expands the channel of a pixel selected by `mask` to 8 bits, by repeating its bits so that
+/// the largest value becomes 255. SDL's own conversions of 16-bit formats can be off by one.
+fn expand_channel(pixel: u32, mask: u32) -> u8 {
+	let shift = mask.trailing_zeros();
+	let bits = (mask >> shift).count_ones();
+	let value = (pixel & mask) >> shift;
+	if bits >= 8 {
+		return (value >> (bits - 8)) as u8;
+	}
+
+	let mut expanded = 0;
+	let mut filled = 0;
+	while filled < 8 {
+		expanded = expanded << bits | value;
+		filled += bits;
+	}
+	(expanded >> (filled - 8)) as u8
+}
+
+/// How the pixels of a surface are stored.
+enum Layout<'a> {
+	/// RGBA with 8 bits per channel, in byte order, to copy as is.
+	Rgba,
+
+	/// Channels packed in the low bytes of a `u32` in native byte order, selected by masks.
+	Packed(PixelMasks),
+
+	/// Indices in a palette of `bits` bits each, packed from the most or least significant
+	/// bit of each byte.
+	Indexed { bits: usize, lsb_first: bool, colors: &'a [sdl2::sys::SDL_Color] },
+}
+
+/// Returns the pixels of a surface as RGBA with 8 bits per channel, with rows from the bottom
+/// like OpenGL expects them.
+///
+/// Formats without alpha become opaque, and the color key becomes transparent.
+pub(crate) fn raw_image_from_surface(
+	surface: &SurfaceRef,
+) -> Result<RawImage2d<'static, u8>, GliumSdl2Error> {
+	let format = surface.pixel_format_enum();
+	let masks = format.into_masks().map_err(GliumSdl2Error::SurfaceError)?;
+
+	// the raw pixel value or palette index of the key, rather than its color, which may map back
+	// to another index of a palette where it appears twice
+	let mut key = 0;
+	let color_key = match unsafe { sdl2::sys::SDL_GetColorKey(surface.raw(), &mut key) } {
+		0 => Some(key),
+		_ => None,
+	};
+
+	let layout = match format {
+		PixelFormatEnum::RGBA32 if color_key.is_none() => Layout::Rgba,
+		PixelFormatEnum::Index1LSB
+		| PixelFormatEnum::Index1MSB
+		| PixelFormatEnum::Index4LSB
+		| PixelFormatEnum::Index4MSB
+		| PixelFormatEnum::Index8 => {
+			// read here rather than converted by SDL2, which reads the LSB formats as if they were MSB
+			let palette = unsafe { (*(*surface.raw()).format).palette };
+			if palette.is_null() {
+				return Err(GliumSdl2Error::SurfaceError("the surface has no palette".into()));
+			}
+			let colors = unsafe { slice::from_raw_parts((*palette).colors, (*palette).ncolors as usize) };
+
+			let lsb_first = format == PixelFormatEnum::Index1LSB || format == PixelFormatEnum::Index4LSB;
+			Layout::Indexed { bits: usize::from(masks.bpp), lsb_first, colors }
+		}
+		_ if masks.rmask != 0 => Layout::Packed(masks),
+		_ => return Err(GliumSdl2Error::SurfaceError(format!("unsupported pixel format {:?}", format))),
+	};
+
+	let (width, height) = surface.size();
+	let width = width as usize;
+	let bits_per_pixel = match layout {
+		Layout::Indexed { bits, .. } => bits,
+		_ => format.byte_size_per_pixel() * 8,
+	};
+	let pitch = surface.pitch() as usize;
+
+	let data = surface.with_lock(|pixels| {
+		let mut data = Vec::with_capacity(width * height as usize * 4);
+
+		// rows may be padded past their pixels
+		for row in (0..height as usize).rev() {
+			let row = &pixels[row * pitch..row * pitch + (width * bits_per_pixel).div_ceil(8)];
+
+			match layout {
+				Layout::Rgba => data.extend_from_slice(row),
+				Layout::Packed(ref masks) => {
+					for bytes in row.chunks(bits_per_pixel / 8) {
+						let mut raw = [0; 4];
+						if cfg!(target_endian = "little") {
+							raw[..bytes.len()].copy_from_slice(bytes);
+						} else {
+							raw[4 - bytes.len()..].copy_from_slice(bytes);
+						}
+						let pixel = u32::from_ne_bytes(raw);
+
+						// like SDL's blitters, which ignore alpha and padding bits to compare keys
+						let rgb = masks.rmask | masks.gmask | masks.bmask;
+						let alpha = match masks.amask {
+							_ if Some(pixel & rgb) == color_key.map(|key| key & rgb) => 0,
+							0 => 255,
+							amask => expand_channel(pixel, amask),
+						};
+						data.extend_from_slice(&[
+							expand_channel(pixel, masks.rmask),
+							expand_channel(pixel, masks.gmask),
+							expand_channel(pixel, masks.bmask),
+							alpha,
+						]);
+					}
+				}
+				Layout::Indexed { bits, lsb_first, colors } => {
+					let per_byte = 8 / bits;
+					for x in 0..width {
+						let slot = x % per_byte;
+						let shift = if lsb_first { slot * bits } else { 8 - bits - slot * bits };
+						let index = u32::from(row[x / per_byte] >> shift) & ((1 << bits) - 1);
+
+						// indices past the end of the palette are black
+						let color = colors.get(index as usize);
+						let rgba = color.map_or([0, 0, 0, 255], |color| [color.r, color.g, color.b, color.a]);
+						let alpha = if Some(index) == color_key { 0 } else { rgba[3] };
+						data.extend_from_slice(&[rgba[0], rgba[1], rgba[2], alpha]);
+					}
+				}
+			}
+		}
+
+		data
+	});
+
+	Ok(RawImage2d::from_raw_rgba(data, (width as u32, height)))
+}
+
+/// Uploads an SDL surface to a texture, such as an image loaded with `SDL_image`,
+/// `Surface::load_bmp` or text rendered with `SDL_ttf`.
+///
+/// The surface can be in any of SDL's RGB formats, including palettized, 16-bit and BGRA
+/// formats. Its color key, if any, becomes transparent. The rows are flipped, so that the top
+/// of the surface is at the top of the texture when drawn with the usual texture coordinates,
+/// where `(0, 0)` is the bottom left.
+pub fn texture_from_surface(facade: &SDL2Facade, surface: &SurfaceRef) -> Result<Texture2d, GliumSdl2Error> {
+	let image = raw_image_from_surface(surface)?;
+	Ok(Texture2d::new(facade, image)?)
+}
+
+/// Copies an image with rows from the bottom, like the ones of glium, into a surface with rows
+/// from the top, in the `RGBA32` or `RGB24` format.
+pub(crate) fn surface_from_raw_image(image: &RawImage2d<u8>) -> Result<Surface<'static>, GliumSdl2Error> {
+	let format = match image.format {
+		ClientFormat::U8U8U8U8 => PixelFormatEnum::RGBA32,
+		ClientFormat::U8U8U8 => PixelFormatEnum::RGB24,
+		format => {
+			let message = format!("unsupported image format {:?}, expected 8-bit RGB or RGBA", format);
+			return Err(GliumSdl2Error::SurfaceError(message));
+		}
+	};
+
+	let row_length = image.width as usize * format.byte_size_per_pixel();
+	if image.data.len() != row_length * image.height as usize {
+		return Err(GliumSdl2Error::SurfaceError("the image data doesn't match its size".into()));
+	}
+
+	let mut surface = Surface::new(image.width, image.height, format).map_err(GliumSdl2Error::SurfaceError)?;
+	let pitch = surface.pitch() as usize;
+
+	// SDL doesn't allocate pixels for empty surfaces
+	if row_length == 0 || image.height == 0 {
+		return Ok(surface);
+	}
+
+	surface.with_lock_mut(|pixels| {
+		for (row, data) in image.data.chunks(row_length).rev().enumerate() {
+			pixels[row * pitch..row * pitch + row_length].copy_from_slice(data);
+		}
+	});
+
+	Ok(surface)
+}
+
+/// Reads a texture back into an SDL surface, in the `RGBA32` format.
+///
+/// This is the reverse of `texture_from_surface`: the bottom row of the texture becomes the
+/// last row of the surface.
+pub fn surface_from_texture(texture: &Texture2d) -> Result<Surface<'static>, GliumSdl2Error> {
+	let image: RawImage2d<u8> = texture.read();
+	surface_from_raw_image(&image)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::surface_from_raw_image;
+	use glium::texture::{ClientFormat, RawImage2d};
+	use std::borrow::Cow;
+
+	#[test]
+	fn empty_images_give_empty_surfaces() {
+		for &(width, height) in &[(0, 4), (4, 0), (0, 0)] {
+			let image = RawImage2d { data: Cow::Owned(Vec::new()), width, height, format: ClientFormat::U8U8U8U8 };
+			let surface = surface_from_raw_image(&image).unwrap();
+			assert_eq!(surface.size(), (width, height));
+		}
+	}
+}
@@ -0,0 +1,234 @@
+//! CPU and GPU frame timing, for `SDL2Facade::draw_timed`.
+
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use glium::backend::Backend;
+use glium::{Frame, SwapBuffersError};
+
+use gl;
+use SDL2WindowBackend;
+
+/// The number of frames that statistics are computed over.
+const HISTORY: usize = 240;
+
+/// The number of GPU queries that can be waiting for their result. The GPU is rarely more than
+/// 2 or 3 frames behind; past this, queries are dropped rather than piling up.
+const MAX_PENDING_QUERIES: usize = 8;
+
+/// Statistics over the last frames, for one of the measurements of `FrameStats`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DurationStats {
+	pub min: Duration,
+	pub avg: Duration,
+	pub max: Duration,
+
+	/// The duration that 99% of the frames didn't exceed.
+	pub p99: Duration,
+}
+
+/// Timings of the frames drawn with `SDL2Facade::draw_timed`, over the last 240 frames.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameStats {
+	/// The number of frames the CPU and swap statistics are computed over.
+	pub frames: usize,
+
+	/// The time between `draw_timed` and `TimedFrame::finish`, spent building the frame.
+	pub cpu: Option<DurationStats>,
+
+	/// The time spent in `finish`, swapping the buffers. This includes waiting for vsync.
+	pub swap: Option<DurationStats>,
+
+	/// The time the GPU spent executing the commands of the frame. `None` if the context
+	/// doesn't support timer queries.
+	///
+	/// Results arrive a few frames late, so this may cover fewer frames than `frames`.
+	pub gpu: Option<DurationStats>,
+}
+
+/// A rolling window of durations.
+struct History {
+	samples: VecDeque<Duration>,
+}
+
+impl History {
+	fn new() -> History {
+		History { samples: VecDeque::with_capacity(HISTORY) }
+	}
+
+	fn push(&mut self, sample: Duration) {
+		if self.samples.len() == HISTORY {
+			self.samples.pop_front();
+		}
+		self.samples.push_back(sample);
+	}
+
+	fn stats(&self) -> Option<DurationStats> {
+		let mut sorted: Vec<Duration> = self.samples.iter().cloned().collect();
+		sorted.sort();
+
+		let len = sorted.len();
+		let p99 = (len * 99).div_ceil(100).checked_sub(1)?;
+
+		Some(DurationStats {
+			min: sorted[0],
+			avg: sorted.iter().sum::<Duration>() / len as u32,
+			max: sorted[len - 1],
+			p99: sorted[p99],
+		})
+	}
+}
+
+/// The timing state of a backend.
+pub(crate) struct FrameTimer {
+	// `None` until the first timed frame, then `Some(None)` if timer queries aren't supported
+	queries: Option<Option<gl::TimerQueries>>,
+	pending: VecDeque<gl::GLuint>,
+	free: Vec<gl::GLuint>,
+	cpu: History,
+	swap: History,
+	gpu: History,
+}
+
+impl FrameTimer {
+	pub(crate) fn new() -> FrameTimer {
+		FrameTimer {
+			queries: None,
+			pending: VecDeque::new(),
+			free: Vec::new(),
+			cpu: History::new(),
+			swap: History::new(),
+			gpu: History::new(),
+		}
+	}
+
+	pub(crate) fn stats(&self) -> FrameStats {
+		FrameStats {
+			frames: self.cpu.samples.len(),
+			cpu: self.cpu.stats(),
+			swap: self.swap.stats(),
+			gpu: self.gpu.stats(),
+		}
+	}
+
+	/// Collects the results of the finished queries, then starts timing a frame on the GPU.
+	///
+	/// The context must be current.
+	fn begin(&mut self, load: impl FnOnce() -> Option<gl::TimerQueries>) {
+		let queries = match *self.queries.get_or_insert_with(load) {
+			Some(queries) => queries,
+			None => return,
+		};
+
+		self.collect(&queries);
+
+		let query = match self.free.pop() {
+			Some(query) => query,
+			None if self.pending.len() < MAX_PENDING_QUERIES => {
+				let mut query = 0;
+				(queries.gen_queries)(1, &mut query);
+				query
+			}
+			// recycle the oldest query, whose result is lost
+			None => self.pending.pop_front().unwrap(),
+		};
+
+		(queries.begin_query)(gl::TIME_ELAPSED, query);
+		self.pending.push_back(query);
+	}
+
+	/// Reads the results of the oldest queries, stopping at the first one that isn't ready.
+	fn collect(&mut self, queries: &gl::TimerQueries) {
+		// a disjoint event (such as a frequency change) makes the results in flight meaningless
+		if let Some(get_integerv) = queries.get_integerv {
+			let mut disjoint = 0;
+			get_integerv(gl::GPU_DISJOINT, &mut disjoint);
+			if disjoint != 0 {
+				self.free.extend(self.pending.drain(..));
+				return;
+			}
+		}
+
+		while let Some(&query) = self.pending.front() {
+			let mut available = 0;
+			(queries.get_query_objectiv)(query, gl::QUERY_RESULT_AVAILABLE, &mut available);
+			if available == 0 {
+				break;
+			}
+
+			let mut nanoseconds = 0;
+			(queries.get_query_objectui64v)(query, gl::QUERY_RESULT, &mut nanoseconds);
+			self.gpu.push(Duration::from_nanos(nanoseconds));
+
+			self.pending.pop_front();
+			self.free.push(query);
+		}
+	}
+
+	/// Stops timing the frame on the GPU. The context must be current.
+	fn end(&mut self) {
+		if let Some(Some(queries)) = self.queries {
+			(queries.end_query)(gl::TIME_ELAPSED);
+		}
+	}
+}
+
+/// A `Frame` whose CPU, swap and GPU times are recorded, obtained from `SDL2Facade::draw_timed`.
+///
+/// It dereferences to the `Frame`, to be drawn on in the same way, and must be finished with
+/// `finish` for its times to be recorded.
+pub struct TimedFrame {
+	frame: Frame,
+	backend: Rc<SDL2WindowBackend>,
+	start: Instant,
+}
+
+impl TimedFrame {
+	pub(crate) fn new(
+		frame: Frame,
+		backend: Rc<SDL2WindowBackend>,
+		load: impl FnOnce() -> Option<gl::TimerQueries>,
+	) -> TimedFrame {
+		let start = Instant::now();
+		backend.frame_timer.borrow_mut().begin(load);
+
+		TimedFrame { frame, backend, start }
+	}
+
+	/// Stops drawing, swaps the buffers, and records the times of the frame.
+	pub fn finish(self) -> Result<(), SwapBuffersError> {
+		let cpu = self.start.elapsed();
+
+		// drawing on another facade may have made its context current
+		if !self.backend.is_current() {
+			unsafe { self.backend.make_current() };
+		}
+		self.backend.frame_timer.borrow_mut().end();
+
+		let swap_start = Instant::now();
+		let result = self.frame.finish();
+		let swap = swap_start.elapsed();
+
+		let mut timer = self.backend.frame_timer.borrow_mut();
+		timer.cpu.push(cpu);
+		timer.swap.push(swap);
+
+		result
+	}
+}
+
+impl Deref for TimedFrame {
+	type Target = Frame;
+
+	fn deref(&self) -> &Frame {
+		&self.frame
+	}
+}
+
+impl DerefMut for TimedFrame {
+	fn deref_mut(&mut self) -> &mut Frame {
+		&mut self.frame
+	}
+}
@@ -0,0 +1,185 @@
+//! Drawing at a fixed resolution, scaled up to the window, for `SDL2Facade::draw_virtual`.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::texture::Texture2d;
+use glium::uniforms::MagnifySamplerFilter;
+use glium::{BlitTarget, Frame, Rect, Surface, SwapBuffersError};
+
+use dpi;
+
+/// How a virtual resolution is scaled to the window.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScaleMode {
+	/// Scales by the largest whole factor that fits in the window, with black bars around.
+	/// Every virtual pixel covers the same number of window pixels, as pixel art needs.
+	///
+	/// Windows smaller than the virtual resolution are handled like `Fit`.
+	Integer,
+
+	/// Scales as much as possible while keeping the aspect ratio, with black bars on two sides.
+	Fit,
+
+	/// Scales to the whole window, distorting the aspect ratio.
+	Stretch,
+
+	/// Scales to cover the whole window while keeping the aspect ratio, cropping two sides.
+	Fill,
+}
+
+/// Where the virtual resolution ends up in the window, for a given scale mode and window size.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VirtualViewport {
+	resolution: (u32, u32),
+	window_size: (u32, u32),
+	drawable_size: (u32, u32),
+
+	// the part of the virtual picture that is shown, in virtual pixels from the bottom left
+	source: Rect,
+
+	// where it is shown, in framebuffer pixels from the bottom left
+	target: Rect,
+}
+
+impl VirtualViewport {
+	pub(crate) fn new(
+		resolution: (u32, u32),
+		mode: ScaleMode,
+		window_size: (u32, u32),
+		drawable_size: (u32, u32),
+	) -> VirtualViewport {
+		let (width, height) = (resolution.0.max(1), resolution.1.max(1));
+		let (screen_width, screen_height) = (drawable_size.0.max(1), drawable_size.1.max(1));
+		let whole = |width, height| Rect { left: 0, bottom: 0, width, height };
+
+		// centers a rectangle of the given size in another one
+		let center = |width: u32, height: u32, outer_width: u32, outer_height: u32| Rect {
+			left: (outer_width - width) / 2,
+			bottom: (outer_height - height) / 2,
+			width,
+			height,
+		};
+
+		let fit = || {
+			let scale =
+				f64::min(f64::from(screen_width) / f64::from(width), f64::from(screen_height) / f64::from(height));
+			let scaled = |length: u32, screen: u32| ((f64::from(length) * scale).round() as u32).clamp(1, screen);
+			center(scaled(width, screen_width), scaled(height, screen_height), screen_width, screen_height)
+		};
+
+		let (source, target) = match mode {
+			ScaleMode::Stretch => (whole(width, height), whole(screen_width, screen_height)),
+			ScaleMode::Integer if screen_width >= width && screen_height >= height => {
+				let scale = u32::min(screen_width / width, screen_height / height);
+				let target = center(width * scale, height * scale, screen_width, screen_height);
+				(whole(width, height), target)
+			}
+			ScaleMode::Integer | ScaleMode::Fit => (whole(width, height), fit()),
+			ScaleMode::Fill => {
+				let scale =
+					f64::max(f64::from(screen_width) / f64::from(width), f64::from(screen_height) / f64::from(height));
+				let visible =
+					|screen: u32, length: u32| ((f64::from(screen) / scale).round() as u32).clamp(1, length);
+				let source = center(visible(screen_width, width), visible(screen_height, height), width, height);
+				(source, whole(screen_width, screen_height))
+			}
+		};
+
+		VirtualViewport { resolution: (width, height), window_size, drawable_size, source, target }
+	}
+
+	/// The virtual resolution.
+	pub fn resolution(&self) -> (u32, u32) {
+		self.resolution
+	}
+
+	/// The rectangle of the window where the virtual picture is shown, in framebuffer pixels
+	/// from the top left. Outside of it are the black bars.
+	pub fn target_rect(&self) -> sdl2::rect::Rect {
+		let top = self.drawable_size.1 as i32 - (self.target.bottom + self.target.height) as i32;
+		sdl2::rect::Rect::new(self.target.left as i32, top, self.target.width, self.target.height)
+	}
+
+	/// Converts a point in window units, such as the position of the mouse in SDL events, into
+	/// virtual pixels from the top left.
+	///
+	/// Returns `None` if the point is on the black bars or outside of the window.
+	pub fn to_virtual_point(&self, point: (i32, i32)) -> Option<(i32, i32)> {
+		let (x, y) = dpi::scale_point(point, self.window_size, self.drawable_size);
+		let target = self.target_rect();
+		if !target.contains_point((x, y)) {
+			return None;
+		}
+
+		// from the top of the virtual picture, rather than from the bottom like `source`
+		let source_top = self.resolution.1 - (self.source.bottom + self.source.height);
+		let scale = |offset: i32, source: u32, target: u32| {
+			(f64::from(offset) * f64::from(source) / f64::from(target)).floor() as i32
+		};
+
+		Some((
+			self.source.left as i32 + scale(x - target.x(), self.source.width, self.target.width),
+			source_top as i32 + scale(y - target.y(), self.source.height, self.target.height),
+		))
+	}
+}
+
+/// A frame drawn at a virtual resolution, obtained from `SDL2Facade::draw_virtual`.
+///
+/// Drawing is done on the texture returned by `surface`, which is scaled to the window when
+/// the frame is finished. It has no depth or stencil buffer.
+pub struct VirtualFrame {
+	frame: Frame,
+	texture: Texture2d,
+	cache: Rc<RefCell<Option<Texture2d>>>,
+	viewport: VirtualViewport,
+	filter: MagnifySamplerFilter,
+}
+
+impl VirtualFrame {
+	pub(crate) fn new(
+		frame: Frame,
+		texture: Texture2d,
+		cache: Rc<RefCell<Option<Texture2d>>>,
+		viewport: VirtualViewport,
+	) -> VirtualFrame {
+		VirtualFrame { frame, texture, cache, viewport, filter: MagnifySamplerFilter::Nearest }
+	}
+
+	/// The surface to draw on, at the virtual resolution.
+	pub fn surface(&self) -> SimpleFrameBuffer<'_> {
+		self.texture.as_surface()
+	}
+
+	/// Where the virtual picture is shown in the window, for this frame.
+	pub fn viewport(&self) -> &VirtualViewport {
+		&self.viewport
+	}
+
+	/// Sets how the picture is filtered when scaled to the window. The default is `Nearest`,
+	/// which keeps pixel art sharp.
+	pub fn set_filter(&mut self, filter: MagnifySamplerFilter) {
+		self.filter = filter;
+	}
+
+	/// Scales the picture to the window, with black bars around it, and swaps the buffers.
+	pub fn finish(self) -> Result<(), SwapBuffersError> {
+		let VirtualFrame { mut frame, texture, cache, viewport, filter } = self;
+
+		let target = BlitTarget {
+			left: viewport.target.left,
+			bottom: viewport.target.bottom,
+			width: viewport.target.width as i32,
+			height: viewport.target.height as i32,
+		};
+		frame.clear_color(0.0, 0.0, 0.0, 1.0);
+		texture.as_surface().blit_color(&viewport.source, &frame, &target, filter);
+
+		// kept for the next frame
+		*cache.borrow_mut() = Some(texture);
+
+		frame.finish()
+	}
+}
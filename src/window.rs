@@ -0,0 +1,177 @@
+//! Borrow tracking for the window shared by a facade and its clones.
+
+use std::cell::{Cell, Ref, RefCell, RefMut};
+use std::ops::{Deref, DerefMut};
+
+use sdl2::video::Window;
+
+/// A window type of which several handles to the same underlying window can be made.
+pub(crate) trait WindowHandle: Sized {
+	fn duplicate(&self) -> Self;
+}
+
+impl WindowHandle for Window {
+	fn duplicate(&self) -> Window {
+		Window::from_ref(self.context())
+	}
+}
+
+/// The window of a backend.
+///
+/// Users borrow the window through `RefCell` guards, so that the clones of a facade can't
+/// alias a mutable reference to it. The backend itself works on a second handle to the same
+/// window, which is never lent out, so that glium can still query and swap the window while
+/// the user holds a guard.
+pub(crate) struct SharedWindow<W> {
+	lent: RefCell<W>,
+	handle: RefCell<W>,
+}
+
+impl<W: WindowHandle> SharedWindow<W> {
+	pub(crate) fn new(window: W) -> SharedWindow<W> {
+		let handle = window.duplicate();
+		SharedWindow { lent: RefCell::new(window), handle: RefCell::new(handle) }
+	}
+
+	/// The window as lent to users.
+	pub(crate) fn lent(&self) -> &RefCell<W> {
+		&self.lent
+	}
+
+	/// The backend's own handle, only to be borrowed for the duration of a backend call.
+	pub(crate) fn handle(&self) -> Ref<'_, W> {
+		self.handle.borrow()
+	}
+
+	pub(crate) fn into_inner(self) -> W {
+		self.lent.into_inner()
+	}
+
+	/// Replaces the window, returning the old one.
+	///
+	/// # Panics
+	///
+	/// Panics if the window is currently borrowed.
+	pub(crate) fn replace(&self, window: W) -> W {
+		let handle = window.duplicate();
+		let old = self.lent.replace(window);
+		drop(self.handle.replace(handle));
+		old
+	}
+}
+
+/// A shared borrow of the window of an `SDL2Facade`, obtained from `SDL2Facade::window`.
+pub struct WindowRef<'a> {
+	pub(crate) window: Ref<'a, Window>,
+}
+
+impl<'a> Deref for WindowRef<'a> {
+	type Target = Window;
+
+	fn deref(&self) -> &Window {
+		&self.window
+	}
+}
+
+/// A mutable borrow of the window of an `SDL2Facade`, obtained from `SDL2Facade::window_mut`.
+///
+/// The window can be resized through this guard without going through
+/// `SDL2Facade::handle_event`: the facade queries its framebuffer dimensions while the guard is
+/// held, and refreshes them when it's dropped.
+pub struct WindowRefMut<'a> {
+	pub(crate) window: RefMut<'a, Window>,
+	pub(crate) framebuffer_dimensions: &'a Cell<(u32, u32)>,
+}
+
+impl<'a> Drop for WindowRefMut<'a> {
+	fn drop(&mut self) {
+		self.framebuffer_dimensions.set(self.window.drawable_size());
+	}
+}
+
+impl<'a> Deref for WindowRefMut<'a> {
+	type Target = Window;
+
+	fn deref(&self) -> &Window {
+		&self.window
+	}
+}
+
+impl<'a> DerefMut for WindowRefMut<'a> {
+	fn deref_mut(&mut self) -> &mut Window {
+		&mut self.window
+	}
+}
+
+// These don't touch SDL, so that they can run under Miri.
+#[cfg(test)]
+mod tests {
+	use super::{SharedWindow, WindowHandle};
+	use std::cell::Cell;
+	use std::rc::Rc;
+
+	/// Stands for an SDL window: the handles share the window's state.
+	struct MockWindow {
+		size: Rc<Cell<(u32, u32)>>,
+	}
+
+	impl MockWindow {
+		fn new(size: (u32, u32)) -> MockWindow {
+			MockWindow { size: Rc::new(Cell::new(size)) }
+		}
+	}
+
+	impl WindowHandle for MockWindow {
+		fn duplicate(&self) -> MockWindow {
+			MockWindow { size: self.size.clone() }
+		}
+	}
+
+	#[test]
+	fn clones_cannot_alias_a_mutable_borrow() {
+		let window = Rc::new(SharedWindow::new(MockWindow::new((800, 600))));
+		let clone = window.clone();
+
+		let guard = window.lent().borrow_mut();
+		assert!(clone.lent().try_borrow().is_err());
+		assert!(clone.lent().try_borrow_mut().is_err());
+		drop(guard);
+
+		let first = window.lent().borrow();
+		let second = clone.lent().borrow();
+		assert_eq!(first.size.get(), second.size.get());
+		assert!(window.lent().try_borrow_mut().is_err());
+	}
+
+	#[test]
+	fn handle_is_usable_while_lent_mutably() {
+		let window = SharedWindow::new(MockWindow::new((800, 600)));
+
+		let guard = window.lent().borrow_mut();
+		guard.size.set((1024, 768));
+
+		// what `get_framebuffer_dimensions` and `resize` do in the middle of a frame
+		assert_eq!(window.handle().size.get(), (1024, 768));
+		window.handle().size.set((640, 480));
+		assert_eq!(guard.size.get(), (640, 480));
+	}
+
+	#[test]
+	fn replace_swaps_both_handles() {
+		let window = SharedWindow::new(MockWindow::new((800, 600)));
+
+		let old = window.replace(MockWindow::new((320, 240)));
+		assert_eq!(old.size.get(), (800, 600));
+		assert_eq!(window.lent().borrow().size.get(), (320, 240));
+		assert_eq!(window.handle().size.get(), (320, 240));
+	}
+
+	#[test]
+	#[should_panic]
+	fn replace_panics_while_borrowed() {
+		let window = SharedWindow::new(MockWindow::new((800, 600)));
+
+		let _guard = window.lent().borrow();
+		window.replace(MockWindow::new((320, 240)));
+	}
+}
@@ -0,0 +1,944 @@
+//! Tests that need a real OpenGL context.
+//!
+//! SDL may only be initialized from one thread, so these run sequentially from a custom `main`
+//! instead of the default test harness. They use SDL's `offscreen` video driver, and are skipped
+//! when no OpenGL implementation is available to it.
+
+extern crate glium;
+extern crate glium_sdl2;
+#[cfg(feature = "image")]
+extern crate image;
+extern crate sdl2;
+
+use glium_sdl2::{DisplayBuild, GlConfig, GliumSdl2Error, HeadlessBuild, SDL2Facade};
+use sdl2::pixels::{Color, Palette, PixelFormatEnum};
+use sdl2::surface::Surface;
+use sdl2::VideoSubsystem;
+
+fn build_display(video: &VideoSubsystem, width: u32, height: u32) -> SDL2Facade {
+	video.window("glium_sdl2 test", width, height).hidden().build_glium().unwrap()
+}
+
+fn resize_updates_framebuffer_dimensions(video: &VideoSubsystem) {
+	let display = build_display(video, 320, 240);
+	assert_eq!(display.get_framebuffer_dimensions(), (320, 240));
+
+	display.resize((640, 480));
+	assert_eq!(display.get_framebuffer_dimensions(), (640, 480));
+
+	display.resize((200, 100));
+	assert_eq!(display.get_framebuffer_dimensions(), (200, 100));
+}
+
+fn resize_never_reaches_zero(video: &VideoSubsystem) {
+	let display = build_display(video, 320, 240);
+
+	display.resize((0, 0));
+	assert_eq!(display.get_framebuffer_dimensions(), (1, 1));
+}
+
+fn resize_then_draw(video: &VideoSubsystem) {
+	use glium::Surface;
+
+	let display = build_display(video, 320, 240);
+	display.resize((400, 300));
+
+	let mut target = display.draw();
+	assert_eq!(target.get_dimensions(), (400, 300));
+	target.clear_color(0.0, 0.0, 0.0, 1.0);
+	target.finish().unwrap();
+}
+
+fn try_make_current_after_switching_contexts(video: &VideoSubsystem) {
+	let first = build_display(video, 64, 64);
+	let second = build_display(video, 64, 64);
+
+	second.try_make_current().unwrap();
+	first.try_make_current().unwrap();
+
+	let target = first.draw();
+	target.finish().unwrap();
+}
+
+fn robust_context_builds_and_draws(video: &VideoSubsystem) {
+	use glium::{CapabilitiesSource, Surface};
+
+	let display = build_display(video, 32, 32);
+	assert!(!display.detects_context_loss());
+
+	glium_sdl2::request_robust_context(video, false).unwrap();
+	let robust = build_display(video, 32, 32);
+	// the attributes are global, and must not leak into the next tests
+	unsafe { sdl2::sys::SDL_GL_ResetAttributes() };
+
+	assert!(robust.context_attributes().robust);
+	// SDL only passes the reset notification strategy on with GLX and WGL, not with EGL
+	assert_eq!(robust.detects_context_loss(), robust.get_capabilities().can_lose_context);
+
+	let mut target = robust.draw();
+	target.clear_color(0.0, 0.0, 0.0, 1.0);
+	target.finish().unwrap();
+	robust.finish();
+}
+
+fn rebuild_keeps_shared_resources(video: &VideoSubsystem) {
+	use glium::Texture2d;
+	use sdl2::video::GLProfile;
+
+	let display = build_display(video, 64, 64);
+	let pixels = vec![vec![(255u8, 0u8, 0u8, 255u8); 2]; 2];
+	let texture = Texture2d::new(&display, pixels.clone()).unwrap();
+
+	video.window("glium_sdl2 test", 128, 96).hidden().rebuild_glium(&display).unwrap();
+	assert_eq!(display.get_framebuffer_dimensions(), (128, 96));
+
+	let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.read();
+	assert_eq!(read_back, pixels);
+
+	let target = display.draw();
+	target.finish().unwrap();
+
+	let config = GlConfig { depth_bits: 24, ..Default::default() };
+	video.window("glium_sdl2 test", 64, 64).hidden().rebuild_glium_with(&config, &display).unwrap();
+	assert!(display.context_attributes().depth_bits >= 24);
+
+	// glium would keep using the version and profile of the old context
+	let version = *display.get_opengl_version();
+	let config = GlConfig { version: Some((2, 1)), profile: Some(GLProfile::GLES), ..Default::default() };
+	match video.window("glium_sdl2 test", 32, 32).hidden().rebuild_glium_with(&config, &display) {
+		Err(GliumSdl2Error::AttributeMismatch(mismatches)) => assert_eq!(mismatches[0].attribute, "version"),
+		Err(GliumSdl2Error::ContextCreationError(_)) => {}
+		_ => panic!("the context was rebuilt with another version"),
+	}
+	assert_eq!(*display.get_opengl_version(), version);
+	assert_eq!(display.get_framebuffer_dimensions(), (64, 64));
+
+	let target = display.draw();
+	target.finish().unwrap();
+}
+
+fn shared_window_uses_the_same_textures(video: &VideoSubsystem) {
+	use glium::framebuffer::SimpleFrameBuffer;
+	use glium::{Surface, Texture2d};
+
+	let main_window = build_display(video, 64, 64);
+	let tool_window =
+		main_window.build_shared_window(video.window("glium_sdl2 test", 32, 32).hidden()).unwrap();
+
+	let texture = Texture2d::empty(&main_window, 2, 2).unwrap();
+	{
+		let mut framebuffer = SimpleFrameBuffer::new(&tool_window, &texture).unwrap();
+		framebuffer.clear_color(0.0, 1.0, 0.0, 1.0);
+	}
+	// commands of different contexts aren't ordered relative to each other
+	tool_window.finish();
+
+	let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.read();
+	assert_eq!(read_back, vec![vec![(0, 255, 0, 255); 2]; 2]);
+
+	for display in &[&main_window, &tool_window] {
+		let mut target = display.draw();
+		target.clear_color(0.0, 0.0, 0.0, 1.0);
+		target.finish().unwrap();
+	}
+}
+
+fn headless_draw_and_read_back(video: &VideoSubsystem) {
+	use glium::Surface;
+
+	let display = video.build_glium_headless(64, 32).unwrap();
+	assert_eq!(display.get_framebuffer_dimensions(), (64, 32));
+
+	let mut target = display.draw();
+	target.clear_color(0.0, 0.0, 1.0, 1.0);
+	target.finish().unwrap();
+
+	let pixels: Vec<Vec<(u8, u8, u8, u8)>> = display.read_front_buffer().unwrap();
+	assert_eq!(pixels.len(), 32);
+	assert!(pixels.iter().all(|row| row.len() == 64 && row.iter().all(|&p| p == (0, 0, 255, 255))));
+
+	// the frame follows the window when it's resized, and can be blitted from
+	display.resize((48, 16));
+	let mut target = display.draw();
+	assert_eq!(target.get_dimensions(), (48, 16));
+	target.clear_color(1.0, 0.0, 0.0, 1.0);
+	let screenshot = display.screenshot_frame(&target).unwrap();
+	target.finish().unwrap();
+	assert_eq!((screenshot.width, screenshot.height), (48, 16));
+	assert!(screenshot.data.chunks(4).all(|pixel| pixel == [255, 0, 0, 255]));
+
+	let pixels: Vec<Vec<(u8, u8, u8, u8)>> = display.read_front_buffer().unwrap();
+	assert_eq!((pixels.len(), pixels[0].len()), (16, 48));
+	assert!(pixels.iter().all(|row| row.iter().all(|&p| p == (255, 0, 0, 255))));
+}
+
+fn gl_config_does_not_leak_into_later_windows(video: &VideoSubsystem) {
+	let gl_attr = video.gl_attr();
+	let previous_version = gl_attr.context_version();
+	let previous_flags = gl_attr.context_flags().has_debug();
+	let plain = build_display(video, 32, 32).context_attributes();
+
+	let config = GlConfig { version: Some((3, 3)), debug: true, depth_bits: 24, ..Default::default() };
+	let display = video.window("glium_sdl2 test", 64, 64).hidden().build_glium_with(&config).unwrap();
+	assert!(*display.get_opengl_version() >= glium::Version(glium::Api::Gl, 3, 3));
+
+	assert_eq!(gl_attr.context_version(), previous_version);
+	assert_eq!(gl_attr.context_flags().has_debug(), previous_flags);
+
+	// framebuffer attributes go back to SDL's defaults, whether the build succeeds or not
+	let config = GlConfig { depth_bits: 24, stencil_bits: 8, multisample_samples: 16, ..Default::default() };
+	let _ = video.window("glium_sdl2 test", 64, 64).hidden().build_glium_with(&config);
+	let attributes = build_display(video, 32, 32).context_attributes();
+	assert_eq!(attributes.multisample_samples, 0);
+	assert_eq!(attributes.depth_bits, plain.depth_bits);
+	assert_eq!(attributes.stencil_bits, plain.stencil_bits);
+	assert_eq!(attributes.double_buffer, plain.double_buffer);
+
+	// robust contexts requested beforehand stay requested
+	glium_sdl2::request_robust_context(video, false).unwrap();
+	video.window("glium_sdl2 test", 64, 64).hidden().build_glium_with(&GlConfig::default()).unwrap();
+	assert!(gl_attr.context_flags().has_robust_access());
+	let robust = build_display(video, 32, 32);
+	unsafe { sdl2::sys::SDL_GL_ResetAttributes() };
+	assert!(robust.context_attributes().robust);
+}
+
+fn context_attributes_report_what_was_obtained(video: &VideoSubsystem) {
+	let config = GlConfig { depth_bits: 24, stencil_bits: 8, ..Default::default() };
+	let display = video.window("glium_sdl2 test", 64, 64).hidden().build_glium_with(&config).unwrap();
+
+	let attributes = display.context_attributes();
+	assert!(attributes.depth_bits >= 24);
+	assert!(attributes.stencil_bits >= 8);
+	assert!(!attributes.renderer.is_empty());
+
+	// the offscreen driver draws to single-buffered pbuffers
+	let config = GlConfig { double_buffer: attributes.double_buffer, ..config };
+	assert!(config.mismatches(&attributes).is_empty());
+	video.window("glium_sdl2 test", 64, 64).hidden().build_glium_strict(&config).unwrap();
+
+	let greedy = GlConfig { depth_bits: 255, ..config };
+	let mismatches = greedy.mismatches(&attributes);
+	assert_eq!(mismatches.len(), 1);
+	assert_eq!(mismatches[0].attribute, "depth bits");
+	assert_eq!(mismatches[0].requested, "255");
+	assert_eq!(mismatches[0].obtained, attributes.depth_bits.to_string());
+}
+
+fn fallback_skips_unavailable_versions(video: &VideoSubsystem) {
+	use sdl2::video::GLProfile;
+
+	let configs = [
+		GlConfig { version: Some((9, 9)), profile: Some(GLProfile::Core), ..Default::default() },
+		GlConfig { version: Some((3, 3)), profile: Some(GLProfile::Core), ..Default::default() },
+		GlConfig::default(),
+	];
+
+	let (display, index) =
+		video.window("glium_sdl2 test", 64, 64).hidden().build_glium_fallback(&configs).unwrap();
+	assert_eq!(index, 1);
+	assert!(*display.get_opengl_version() >= glium::Version(glium::Api::Gl, 3, 3));
+
+	let target = display.draw();
+	target.finish().unwrap();
+}
+
+fn vsync_reports_the_applied_mode(video: &VideoSubsystem) {
+	use glium_sdl2::Vsync;
+
+	let first = build_display(video, 64, 64);
+	let second = build_display(video, 64, 64);
+
+	for &requested in &[Vsync::Off, Vsync::On, Vsync::Adaptive] {
+		// the driver may not support some modes, but must report the ones it applied
+		if let Ok(applied) = first.set_vsync(requested) {
+			second.try_make_current().unwrap();
+			assert_eq!(first.vsync().unwrap(), applied);
+		}
+	}
+}
+
+fn window_guard_held_across_a_frame(video: &VideoSubsystem) {
+	use glium::Surface;
+
+	let display = build_display(video, 64, 48);
+	let clone = display.clone();
+
+	let mut window = display.window_mut();
+	window.set_title("glium_sdl2 guarded").unwrap();
+	assert!(clone.try_window().is_err());
+	assert!(clone.try_window_mut().is_err());
+
+	let mut target = clone.draw();
+	assert_eq!(target.get_dimensions(), (64, 48));
+	target.clear_color(0.0, 0.0, 0.0, 1.0);
+	target.finish().unwrap();
+
+	// resized while the guard is still held
+	window.set_size(80, 40).unwrap();
+	let mut target = clone.draw();
+	assert_eq!(target.get_dimensions(), (80, 40));
+	target.clear_color(0.0, 0.0, 0.0, 1.0);
+	target.finish().unwrap();
+	drop(window);
+	assert_eq!(display.get_framebuffer_dimensions(), (80, 40));
+
+	assert_eq!(clone.window().title(), "glium_sdl2 guarded");
+}
+
+fn facade_from_an_existing_window(video: &VideoSubsystem) {
+	use glium::Surface;
+
+	let window = video.window("glium_sdl2 test", 48, 32).hidden().opengl().build().unwrap();
+	let display = SDL2Facade::from_window(window).unwrap();
+	let mut target = display.draw();
+	assert_eq!(target.get_dimensions(), (48, 32));
+	target.clear_color(0.0, 0.0, 0.0, 1.0);
+	target.finish().unwrap();
+
+	let clone = display.clone();
+	let display = match display.into_window() {
+		Ok(_) => panic!("the window was given back while a clone was alive"),
+		Err(display) => display,
+	};
+	drop(clone);
+	let window = display.into_window().ok().unwrap();
+	assert_eq!(window.size(), (48, 32));
+
+	let gl_context = window.gl_create_context().unwrap();
+	let display = unsafe { SDL2Facade::from_window_and_context(window, gl_context).unwrap() };
+	let mut target = display.draw();
+	target.clear_color(0.0, 0.0, 0.0, 1.0);
+	target.finish().unwrap();
+}
+
+fn close_lists_what_still_uses_the_facade(video: &VideoSubsystem) {
+	let display = build_display(video, 32, 32);
+	let clones = vec![display.clone(), display.clone(), display.clone()];
+	let texture = glium::texture::Texture2d::empty(&display, 4, 4).unwrap();
+
+	let err = display.close().unwrap_err();
+	assert_eq!(err.outstanding_clones(), 3);
+	assert_eq!(err.outstanding_objects(), 1);
+	let display = err.into_facade();
+
+	// any clone can be the one that closes the facade
+	drop(display);
+	let mut clones = clones.into_iter();
+	let display = clones.next().unwrap();
+	let err = display.close().unwrap_err();
+	assert_eq!(err.outstanding_clones(), 2);
+	let display = err.into_facade();
+
+	drop(clones);
+	drop(texture);
+
+	// timed frames hold the window too, but aren't clones
+	let frame = display.draw_timed();
+	let err = display.close().unwrap_err();
+	assert_eq!((err.outstanding_clones(), err.outstanding_objects()), (0, 1));
+	let display = err.into_facade();
+	frame.finish().unwrap();
+
+	display.close().unwrap();
+}
+
+fn logical_and_drawable_sizes(video: &VideoSubsystem) {
+	use sdl2::event::{Event, WindowEvent};
+	use sdl2::rect::Rect;
+
+	let config = GlConfig { high_dpi: true, ..GlConfig::default() };
+	let display = video.window("glium_sdl2 test", 80, 60).hidden().build_glium_with(&config).unwrap();
+
+	// the offscreen driver has no HiDPI displays
+	assert_eq!(display.logical_size(), (80, 60));
+	assert_eq!(display.drawable_size(), (80, 60));
+	assert_eq!(display.scale_factor(), 1.0);
+	assert_eq!(display.to_drawable_point((-5, 30)), (-5, 30));
+	assert_eq!(display.to_logical_rect(Rect::new(1, 2, 3, 4)), Rect::new(1, 2, 3, 4));
+
+	let window_id = display.window().id();
+	let moved =
+		|window_id| Event::Window { timestamp: 0, window_id, win_event: WindowEvent::DisplayChanged(1) };
+	assert_eq!(display.scale_factor_changed(&moved(window_id)), None);
+	assert_eq!(display.scale_factor_changed(&moved(window_id + 1)), None);
+}
+
+fn framebuffer_dimensions_follow_window_events(video: &VideoSubsystem) {
+	use glium::Surface;
+	use sdl2::event::{Event, WindowEvent};
+
+	let display = build_display(video, 64, 64);
+
+	display.window_mut().set_size(100, 50).unwrap();
+	assert_eq!(display.get_framebuffer_dimensions(), (100, 50));
+
+	// as if the user resized the window
+	unsafe { sdl2::sys::SDL_SetWindowSize(display.window().raw(), 90, 70) };
+	let window_id = display.window().id();
+	let other_window =
+		Event::Window { timestamp: 0, window_id: window_id + 1, win_event: WindowEvent::SizeChanged(90, 70) };
+	display.handle_event(&other_window);
+	display.handle_event(&Event::Window {
+		timestamp: 0,
+		window_id,
+		win_event: WindowEvent::SizeChanged(90, 70),
+	});
+	assert_eq!(display.get_framebuffer_dimensions(), (90, 70));
+	let target = display.draw();
+	assert_eq!(target.get_dimensions(), (90, 70));
+	target.finish().unwrap();
+
+	// moving to a display with another scale changes the drawable size without a resize event
+	for &(size, win_event) in
+		&[((80, 60), WindowEvent::DisplayChanged(0)), ((70, 50), WindowEvent::Moved(10, 10))]
+	{
+		unsafe { sdl2::sys::SDL_SetWindowSize(display.window().raw(), size.0, size.1) };
+		display.handle_event(&Event::Window { timestamp: 0, window_id, win_event });
+		let target = display.draw();
+		assert_eq!(target.get_dimensions(), (size.0 as u32, size.1 as u32));
+		target.finish().unwrap();
+	}
+}
+
+fn errors_keep_their_cause(video: &VideoSubsystem) {
+	use sdl2::video::GLProfile;
+	use std::error::Error;
+
+	let config = GlConfig { version: Some((9, 9)), profile: Some(GLProfile::Core), ..GlConfig::default() };
+	let err = match video.window("glium_sdl2 test", 64, 64).hidden().build_glium_with(&config) {
+		Ok(_) => panic!("an OpenGL 9.9 context was created"),
+		Err(err) => err,
+	};
+
+	match err {
+		GliumSdl2Error::WindowBuildError(ref cause) => {
+			assert_eq!(err.source().unwrap().to_string(), cause.to_string());
+		}
+		GliumSdl2Error::ContextCreationError(_) => assert!(err.source().is_none()),
+		ref err => panic!("unexpected error: {:?}", err),
+	}
+}
+
+fn diagnostics_describe_the_context(video: &VideoSubsystem) {
+	let diagnostics = glium_sdl2::facade_diagnostics(video);
+	assert_eq!(diagnostics.video_driver, "offscreen");
+	assert!(diagnostics.error.is_none());
+	assert!(diagnostics.missing_extensions.is_empty());
+	assert!(diagnostics.requested_attributes.iter().any(|(name, _)| name == "SDL_GL_CONTEXT_FLAGS"));
+	let context = diagnostics.context.clone().unwrap();
+	assert!(!context.renderer.is_empty());
+	assert!(diagnostics.to_string().contains("video driver: offscreen"));
+
+	let display = build_display(video, 32, 32);
+	let attributes = display.context_attributes();
+	let context = display.diagnostics().unwrap().context.unwrap();
+	assert_eq!(context.renderer, attributes.renderer);
+	assert_eq!(context.version, attributes.version_string);
+}
+
+fn timed_frames_report_their_times(video: &VideoSubsystem) {
+	use glium::{CapabilitiesSource, Surface};
+
+	let display = build_display(video, 64, 64);
+	assert_eq!(display.frame_stats().frames, 0);
+	assert_eq!(display.frame_stats().cpu, None);
+
+	for _ in 0..10 {
+		let mut target = display.draw_timed();
+		target.clear_color(0.0, 0.0, 0.0, 1.0);
+		target.finish().unwrap();
+		display.finish();
+	}
+
+	let stats = display.frame_stats();
+	assert_eq!(stats.frames, 10);
+	let cpu = stats.cpu.unwrap();
+	assert!(cpu.min <= cpu.avg && cpu.avg <= cpu.max);
+	assert!(cpu.min <= cpu.p99 && cpu.p99 <= cpu.max);
+	assert!(stats.swap.is_some());
+
+	// the results of finished queries are collected when the next frame starts
+	if display.get_extensions().gl_arb_timer_query {
+		assert_eq!(stats.gpu.map(|_| ()), Some(()));
+	}
+}
+
+fn screenshots_are_top_down(video: &VideoSubsystem) {
+	use glium::{Rect, Surface};
+
+	let display = build_display(video, 8, 6);
+	let draw = |target: &mut glium::Frame| {
+		target.clear_color(1.0, 0.0, 0.0, 1.0);
+		// the top two rows, in OpenGL's bottom-up coordinates
+		let top = Rect { left: 0, bottom: 4, width: 8, height: 2 };
+		target.clear(Some(&top), Some((0.0, 1.0, 0.0, 1.0)), false, None, None);
+	};
+	let check = |screenshot: &glium_sdl2::Screenshot| {
+		assert_eq!((screenshot.width, screenshot.height), (8, 6));
+		assert_eq!(screenshot.data.len(), 8 * 6 * 4);
+		assert_eq!(&screenshot.data[..4], &[0, 255, 0, 255]);
+		assert_eq!(&screenshot.data[8 * 2 * 4..8 * 2 * 4 + 4], &[255, 0, 0, 255]);
+		assert_eq!(&screenshot.data[screenshot.data.len() - 4..], &[255, 0, 0, 255]);
+	};
+
+	let mut target = display.draw();
+	draw(&mut target);
+	check(&display.screenshot_frame(&target).unwrap());
+	target.finish().unwrap();
+	check(&display.screenshot().unwrap());
+
+	#[cfg(feature = "image")]
+	{
+		let path = std::env::temp_dir().join("glium_sdl2_screenshot.png");
+		display.save_screenshot(&path).unwrap();
+		let image = image::open(&path).unwrap().to_rgba8();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(image.into_raw(), display.screenshot().unwrap().data);
+	}
+}
+
+fn async_readback_hands_frames_back_later(video: &VideoSubsystem) {
+	use glium::Surface;
+
+	let display = build_display(video, 8, 6);
+	let mut readback = display.async_readback(2).unwrap();
+	// one color per frame, with channels of 0 or 255 to be exact after the conversions
+	let color =
+		|index: u64| [(index & 1) as u8 * 255, (index >> 1 & 1) as u8 * 255, (index >> 2 & 1) as u8 * 255, 255];
+
+	let mut received = Vec::new();
+	for index in 0..5 {
+		let mut target = display.draw();
+		let [r, g, b, _] = color(index);
+		target.clear_color(f32::from(r) / 255.0, f32::from(g) / 255.0, f32::from(b) / 255.0, 1.0);
+
+		let frame = readback.queue(&target).unwrap();
+		target.finish().unwrap();
+
+		if index < 2 {
+			assert!(frame.is_none());
+		} else {
+			assert_eq!(frame.as_ref().unwrap().index, index - 2);
+		}
+		received.extend(frame);
+	}
+
+	assert_eq!(readback.in_flight(), 2);
+	received.extend(readback.drain().unwrap());
+	assert_eq!(readback.in_flight(), 0);
+	assert!(readback.try_next().unwrap().is_none());
+
+	assert_eq!(received.len(), 5);
+	for (index, frame) in received.iter().enumerate() {
+		assert_eq!(frame.index, index as u64);
+		assert_eq!((frame.image.width, frame.image.height), (8, 6));
+		assert_eq!(&frame.image.data[..4], &color(index as u64));
+	}
+
+	// frames of a new size get textures of their own
+	display.window_mut().set_size(4, 4).unwrap();
+	let target = display.draw();
+	readback.queue(&target).unwrap();
+	target.finish().unwrap();
+	let frames = readback.drain().unwrap();
+	assert_eq!((frames[0].image.width, frames[0].image.height), (4, 4));
+}
+
+fn frame_recorder_writes_y4m(video: &VideoSubsystem) {
+	use glium::{Rect, Surface};
+	use glium_sdl2::FrameRecorder;
+	use std::time::Duration;
+
+	let display = build_display(video, 8, 6);
+	let path = std::env::temp_dir().join("glium_sdl2_recording.y4m");
+	let mut recorder = FrameRecorder::create_y4m(&display, &path, 30).unwrap();
+	recorder.set_every(2);
+	assert_eq!(recorder.timestep(), Duration::from_secs(1) / 60);
+
+	for index in 0..4 {
+		let mut target = display.draw();
+		if index == 0 {
+			target.clear_color(1.0, 1.0, 1.0, 1.0);
+		} else {
+			// green on the top two rows, red below
+			target.clear_color(1.0, 0.0, 0.0, 1.0);
+			let top = Rect { left: 0, bottom: 4, width: 8, height: 2 };
+			target.clear(Some(&top), Some((0.0, 1.0, 0.0, 1.0)), false, None, None);
+		}
+		assert_eq!(recorder.record(&target).unwrap(), index % 2 == 0);
+		target.finish().unwrap();
+	}
+
+	assert_eq!(recorder.frames_written(), 2);
+	assert_eq!(recorder.duration(), Duration::from_secs(2) / 30);
+	recorder.finish().unwrap();
+
+	let data = std::fs::read(&path).unwrap();
+	std::fs::remove_file(&path).unwrap();
+
+	let header = b"YUV4MPEG2 W8 H6 F30:1 Ip A1:1 C420jpeg XCOLORRANGE=FULL\n";
+	assert_eq!(&data[..header.len()], &header[..]);
+	let frame_size = 6 + 8 * 6 + 2 * 4 * 3;
+	assert_eq!(data.len(), header.len() + 2 * frame_size);
+
+	let frames: Vec<&[u8]> = data[header.len()..].chunks(frame_size).collect();
+	let close = |actual: u8, expected: u8| (i32::from(actual) - i32::from(expected)).abs() <= 1;
+	for frame in &frames {
+		assert_eq!(&frame[..6], b"FRAME\n");
+	}
+
+	// white: full luma, neutral chroma
+	let (luma, chroma) = frames[0][6..].split_at(8 * 6);
+	assert!(luma.iter().all(|&y| y == 255));
+	assert!(chroma.iter().all(|&c| close(c, 128)));
+
+	// the first rows are the top of the frame
+	let (luma, chroma) = frames[1][6..].split_at(8 * 6);
+	assert!(close(luma[0], 150) && close(luma[8 * 6 - 1], 76));
+	let (cb, cr) = chroma.split_at(4 * 3);
+	assert!(close(cb[0], 43) && close(cr[0], 21));
+	assert!(close(cb[4 * 3 - 1], 85) && close(cr[4 * 3 - 1], 255));
+
+	#[cfg(feature = "image")]
+	{
+		let directory = std::env::temp_dir().join("glium_sdl2_recording");
+		let mut recorder = FrameRecorder::png_sequence(&display, &directory, 30).unwrap();
+		for _ in 0..2 {
+			let mut target = display.draw();
+			target.clear_color(0.0, 0.0, 1.0, 1.0);
+			recorder.record(&target).unwrap();
+			target.finish().unwrap();
+		}
+		recorder.finish().unwrap();
+
+		let image = image::open(directory.join("000001.png")).unwrap().to_rgba8();
+		std::fs::remove_dir_all(&directory).unwrap();
+		assert_eq!(image.dimensions(), (8, 6));
+		assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255, 255]);
+	}
+}
+
+fn virtual_resolution_is_scaled_to_the_window(video: &VideoSubsystem) {
+	use glium::{Rect, Surface};
+	use glium_sdl2::ScaleMode;
+	use sdl2::rect::Rect as SdlRect;
+
+	let display = build_display(video, 10, 7);
+
+	let viewport = |mode| display.virtual_viewport((4, 3), mode).target_rect();
+	assert_eq!(viewport(ScaleMode::Integer), SdlRect::new(1, 1, 8, 6));
+	assert_eq!(viewport(ScaleMode::Fit), SdlRect::new(0, 0, 9, 7));
+	assert_eq!(viewport(ScaleMode::Stretch), SdlRect::new(0, 0, 10, 7));
+	assert_eq!(viewport(ScaleMode::Fill), SdlRect::new(0, 0, 10, 7));
+
+	// red, with a green pixel at the top left
+	for _ in 0..2 {
+		let target = display.draw_virtual((4, 3), ScaleMode::Integer).unwrap();
+		let mut surface = target.surface();
+		surface.clear_color(1.0, 0.0, 0.0, 1.0);
+		let top_left = Rect { left: 0, bottom: 2, width: 1, height: 1 };
+		surface.clear(Some(&top_left), Some((0.0, 1.0, 0.0, 1.0)), false, None, None);
+		target.finish().unwrap();
+	}
+
+	let screenshot = display.screenshot().unwrap();
+	let pixel = |x: usize, y: usize| &screenshot.data[(y * 10 + x) * 4..(y * 10 + x) * 4 + 4];
+	assert_eq!(pixel(0, 0), &[0, 0, 0, 255]);
+	assert_eq!(pixel(5, 0), &[0, 0, 0, 255]);
+	assert_eq!(pixel(1, 1), &[0, 255, 0, 255]);
+	assert_eq!(pixel(2, 2), &[0, 255, 0, 255]);
+	assert_eq!(pixel(3, 1), &[255, 0, 0, 255]);
+	assert_eq!(pixel(8, 6), &[255, 0, 0, 255]);
+	assert_eq!(pixel(9, 3), &[0, 0, 0, 255]);
+
+	let viewport = display.virtual_viewport((4, 3), ScaleMode::Integer);
+	assert_eq!(viewport.to_virtual_point((1, 1)), Some((0, 0)));
+	assert_eq!(viewport.to_virtual_point((8, 6)), Some((3, 2)));
+	assert_eq!(viewport.to_virtual_point((0, 3)), None);
+	assert_eq!(viewport.to_virtual_point((5, 0)), None);
+
+	// the viewport follows the size of the window, and fill crops the top and bottom
+	display.window_mut().set_size(8, 4).unwrap();
+	let viewport = display.virtual_viewport((4, 3), ScaleMode::Fill);
+	assert_eq!(viewport.target_rect(), SdlRect::new(0, 0, 8, 4));
+	assert_eq!(viewport.to_virtual_point((0, 0)), Some((0, 1)));
+	assert_eq!(viewport.to_virtual_point((7, 3)), Some((3, 2)));
+
+	let target = display.draw_virtual((4, 3), ScaleMode::Fill).unwrap();
+	assert_eq!(target.viewport(), &viewport);
+	target.surface().clear_color(0.0, 0.0, 1.0, 1.0);
+	target.finish().unwrap();
+	assert!(display.screenshot().unwrap().data.chunks(4).all(|pixel| pixel == [0, 0, 255, 255]));
+}
+
+/// Creates a surface in exactly the given format, which `Surface::new` doesn't guarantee for
+/// the palettized ones.
+fn surface_with_format(width: u32, height: u32, format: PixelFormatEnum) -> Surface<'static> {
+	let raw =
+		unsafe { sdl2::sys::SDL_CreateRGBSurfaceWithFormat(0, width as i32, height as i32, 0, format as u32) };
+	assert!(!raw.is_null(), "{:?}: {}", format, sdl2::get_error());
+
+	let surface = unsafe { Surface::from_ll(raw) };
+	assert_eq!(surface.pixel_format_enum(), format);
+	surface
+}
+
+/// Writes pixels of `bits` bits each, packed from the most or least significant bit of each
+/// byte, or in native byte order past 8 bits.
+fn write_pixels(surface: &mut Surface, bits: usize, lsb_first: bool, pixels: &[u32]) {
+	let width = surface.width() as usize;
+	let pitch = surface.pitch() as usize;
+
+	surface.with_lock_mut(|data| {
+		for (i, &pixel) in pixels.iter().enumerate() {
+			let row = &mut data[i / width * pitch..];
+			let x = i % width;
+			if bits < 8 {
+				let per_byte = 8 / bits;
+				let slot = x % per_byte;
+				let shift = if lsb_first { slot * bits } else { 8 - bits - slot * bits };
+				row[x / per_byte] |= (pixel as u8) << shift;
+			} else {
+				let bytes = bits / 8;
+				row[x * bytes..x * bytes + bytes].copy_from_slice(&pixel.to_ne_bytes()[..bytes]);
+			}
+		}
+	});
+}
+
+fn surfaces_convert_to_textures(video: &VideoSubsystem) {
+	use glium_sdl2::{surface_from_texture, texture_from_surface};
+
+	let display = build_display(video, 8, 6);
+
+	const RED: [u8; 4] = [255, 0, 0, 255];
+	const GREEN: [u8; 4] = [0, 255, 0, 255];
+	const BLUE: [u8; 4] = [0, 0, 255, 255];
+	const WHITE: [u8; 4] = [255, 255, 255, 255];
+	const BLACK: [u8; 4] = [0, 0, 0, 255];
+	const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+	// 3x2, so that rows are padded in most formats; from the top left
+	let colors = [RED, GREEN, BLUE, WHITE, BLACK, CLEAR];
+
+	// the texture has the top row of the surface last, as OpenGL reads rows from the bottom
+	let check = |surface: &Surface, expected: &[[u8; 4]]| {
+		let texture = texture_from_surface(&display, surface).unwrap();
+		let image: glium::texture::RawImage2d<u8> = texture.read();
+		assert_eq!((image.width, image.height), (3, 2));
+		let rows: Vec<&[u8]> = image.data.chunks(3 * 4).rev().collect();
+		let expected: Vec<u8> = expected.iter().flat_map(|pixel| pixel.iter().cloned()).collect();
+		assert_eq!(rows.concat(), expected, "{:?}", surface.pixel_format_enum());
+
+		// and back
+		let back = surface_from_texture(&texture).unwrap();
+		assert_eq!(back.size(), (3, 2));
+		let pitch = back.pitch() as usize;
+		let data = back.with_lock(|data| [&data[..12], &data[pitch..pitch + 12]].concat());
+		assert_eq!(data, expected);
+	};
+
+	// the packed formats, encoded with their channel masks
+	let packed = [
+		PixelFormatEnum::RGB332,
+		PixelFormatEnum::RGB444,
+		PixelFormatEnum::RGB555,
+		PixelFormatEnum::BGR555,
+		PixelFormatEnum::ARGB4444,
+		PixelFormatEnum::RGBA4444,
+		PixelFormatEnum::ABGR4444,
+		PixelFormatEnum::BGRA4444,
+		PixelFormatEnum::ARGB1555,
+		PixelFormatEnum::RGBA5551,
+		PixelFormatEnum::ABGR1555,
+		PixelFormatEnum::BGRA5551,
+		PixelFormatEnum::RGB565,
+		PixelFormatEnum::BGR565,
+		PixelFormatEnum::RGB888,
+		PixelFormatEnum::RGBX8888,
+		PixelFormatEnum::BGR888,
+		PixelFormatEnum::BGRX8888,
+		PixelFormatEnum::ARGB8888,
+		PixelFormatEnum::RGBA8888,
+		PixelFormatEnum::ABGR8888,
+		PixelFormatEnum::BGRA8888,
+		PixelFormatEnum::ARGB2101010,
+	];
+	for &format in &packed {
+		let masks = format.into_masks().unwrap();
+		let encode = |color: &[u8; 4]| {
+			let channel = |value: u8, mask: u32| if value == 255 { mask } else { 0 };
+			channel(color[0], masks.rmask)
+				| channel(color[1], masks.gmask)
+				| channel(color[2], masks.bmask)
+				| channel(color[3], masks.amask)
+		};
+
+		let mut surface = surface_with_format(3, 2, format);
+		let pixels: Vec<u32> = colors.iter().map(encode).collect();
+		write_pixels(&mut surface, format.byte_size_per_pixel() * 8, false, &pixels);
+
+		// without alpha, the transparent pixel is opaque black
+		let expected: Vec<[u8; 4]> =
+			colors.iter().map(|&color| if masks.amask == 0 && color == CLEAR { BLACK } else { color }).collect();
+		check(&surface, &expected);
+	}
+
+	// the formats stored as an array of bytes
+	for &(format, order) in &[(PixelFormatEnum::RGB24, [0, 1, 2]), (PixelFormatEnum::BGR24, [2, 1, 0])] {
+		let mut surface = surface_with_format(3, 2, format);
+		let pitch = surface.pitch() as usize;
+		surface.with_lock_mut(|data| {
+			for (i, color) in colors.iter().enumerate() {
+				let pixel = &mut data[i / 3 * pitch + i % 3 * 3..];
+				for (byte, &channel) in order.iter().enumerate() {
+					pixel[byte] = color[channel];
+				}
+			}
+		});
+
+		let expected: Vec<[u8; 4]> =
+			colors.iter().map(|&color| if color == CLEAR { BLACK } else { color }).collect();
+		check(&surface, &expected);
+	}
+
+	// the palettized formats
+	let palette = Palette::with_colors(&[
+		Color::RGB(255, 0, 0),
+		Color::RGB(0, 255, 0),
+		Color::RGB(0, 0, 255),
+		Color::RGB(255, 255, 255),
+		Color::RGB(0, 0, 0),
+		Color::RGBA(0, 0, 0, 0),
+	])
+	.unwrap();
+	let indices = [0, 1, 2, 3, 4, 5];
+	for &(format, bits, lsb_first) in &[
+		(PixelFormatEnum::Index8, 8, false),
+		(PixelFormatEnum::Index4MSB, 4, false),
+		(PixelFormatEnum::Index4LSB, 4, true),
+	] {
+		let mut surface = surface_with_format(3, 2, format);
+		surface.set_palette(&palette).unwrap();
+		write_pixels(&mut surface, bits, lsb_first, &indices);
+		check(&surface, &colors);
+	}
+
+	// two colors at most, and the color key becomes transparent
+	let palette = Palette::with_colors(&[Color::RGB(255, 255, 255), Color::RGB(0, 0, 0)]).unwrap();
+	for &(format, lsb_first) in &[(PixelFormatEnum::Index1MSB, false), (PixelFormatEnum::Index1LSB, true)] {
+		let mut surface = surface_with_format(3, 2, format);
+		surface.set_palette(&palette).unwrap();
+		write_pixels(&mut surface, 1, lsb_first, &[0, 1, 0, 1, 0, 1]);
+		surface.set_color_key(true, Color::RGB(0, 0, 0)).unwrap();
+		check(&surface, &[WHITE, CLEAR, WHITE, CLEAR, WHITE, CLEAR]);
+	}
+
+	// the key is an index, whose color may appear earlier in the palette
+	let palette =
+		Palette::with_colors(&[Color::RGB(255, 255, 255), Color::RGB(0, 0, 0), Color::RGB(0, 0, 0)]).unwrap();
+	let mut surface = surface_with_format(3, 2, PixelFormatEnum::Index8);
+	surface.set_palette(&palette).unwrap();
+	write_pixels(&mut surface, 8, false, &[0, 1, 2, 2, 1, 0]);
+	assert_eq!(unsafe { sdl2::sys::SDL_SetColorKey(surface.raw(), 1, 2) }, 0);
+	check(&surface, &[WHITE, BLACK, CLEAR, CLEAR, BLACK, WHITE]);
+
+	// padding bits don't count when comparing with the key
+	let mut surface = surface_with_format(3, 2, PixelFormatEnum::RGB888);
+	write_pixels(
+		&mut surface,
+		32,
+		false,
+		&[0xab00_0000, 0x00ff_ffff, 0xff00_0000, 0, 0x12ff_ffff, 0x0000_0000],
+	);
+	surface.set_color_key(true, Color::RGB(0, 0, 0)).unwrap();
+	check(&surface, &[CLEAR, WHITE, CLEAR, CLEAR, WHITE, CLEAR]);
+}
+
+fn icon_and_cursor_from_images(video: &VideoSubsystem) {
+	use glium::texture::RawImage2d;
+	use std::time::Duration;
+
+	let display = build_display(video, 8, 6);
+
+	let pixels: Vec<u8> = (0..4).flat_map(|i| vec![i * 60, 0, 0, 255]).collect();
+	display.set_icon_from_image(RawImage2d::from_raw_rgba(pixels.clone(), (2, 2))).unwrap();
+	match display.set_icon_from_image(RawImage2d::from_raw_rgba(pixels.clone(), (3, 2))) {
+		Err(GliumSdl2Error::SurfaceError(_)) => (),
+		_ => panic!("an image of the wrong size was accepted"),
+	}
+
+	let texture = glium::Texture2d::new(&display, RawImage2d::from_raw_rgba(pixels, (2, 2))).unwrap();
+	let cursor = match display.set_cursor_from_texture(&texture, (1, 1)) {
+		Ok(cursor) => cursor,
+		Err(GliumSdl2Error::CursorError(err)) => {
+			eprintln!("skipping cursors: {}", err);
+			return;
+		}
+		Err(err) => panic!("{}", err),
+	};
+	assert_eq!(cursor.frames(), 1);
+
+	let frame = Duration::from_millis(100);
+	let frames = [(&texture, frame), (&texture, frame), (&texture, frame)];
+	let mut cursor = display.set_animated_cursor(&frames, (0, 0)).unwrap();
+	assert_eq!(cursor.frames(), 3);
+	cursor.update(Duration::from_millis(150));
+	assert_eq!(cursor.current_frame(), 1);
+}
+
+type TestCase = (&'static str, fn(&VideoSubsystem));
+
+fn main() {
+	sdl2::hint::set("SDL_VIDEODRIVER", "offscreen");
+
+	let sdl_context = sdl2::init().unwrap();
+	let video = match sdl_context.video() {
+		Ok(video) => video,
+		Err(err) => {
+			eprintln!("skipping offscreen tests: {}", err);
+			return;
+		}
+	};
+
+	if let Err(err) = video.window("glium_sdl2 probe", 16, 16).hidden().build_glium() {
+		eprintln!("skipping offscreen tests: {}", err);
+		return;
+	}
+
+	let tests: &[TestCase] = &[
+		("resize_updates_framebuffer_dimensions", resize_updates_framebuffer_dimensions),
+		("resize_never_reaches_zero", resize_never_reaches_zero),
+		("resize_then_draw", resize_then_draw),
+		("try_make_current_after_switching_contexts", try_make_current_after_switching_contexts),
+		("robust_context_builds_and_draws", robust_context_builds_and_draws),
+		("rebuild_keeps_shared_resources", rebuild_keeps_shared_resources),
+		("shared_window_uses_the_same_textures", shared_window_uses_the_same_textures),
+		("headless_draw_and_read_back", headless_draw_and_read_back),
+		("gl_config_does_not_leak_into_later_windows", gl_config_does_not_leak_into_later_windows),
+		("context_attributes_report_what_was_obtained", context_attributes_report_what_was_obtained),
+		("fallback_skips_unavailable_versions", fallback_skips_unavailable_versions),
+		("vsync_reports_the_applied_mode", vsync_reports_the_applied_mode),
+		("window_guard_held_across_a_frame", window_guard_held_across_a_frame),
+		("facade_from_an_existing_window", facade_from_an_existing_window),
+		("close_lists_what_still_uses_the_facade", close_lists_what_still_uses_the_facade),
+		("logical_and_drawable_sizes", logical_and_drawable_sizes),
+		("framebuffer_dimensions_follow_window_events", framebuffer_dimensions_follow_window_events),
+		("errors_keep_their_cause", errors_keep_their_cause),
+		("diagnostics_describe_the_context", diagnostics_describe_the_context),
+		("timed_frames_report_their_times", timed_frames_report_their_times),
+		("screenshots_are_top_down", screenshots_are_top_down),
+		("async_readback_hands_frames_back_later", async_readback_hands_frames_back_later),
+		("frame_recorder_writes_y4m", frame_recorder_writes_y4m),
+		("virtual_resolution_is_scaled_to_the_window", virtual_resolution_is_scaled_to_the_window),
+		("surfaces_convert_to_textures", surfaces_convert_to_textures),
+		("icon_and_cursor_from_images", icon_and_cursor_from_images),
+	];
+
+	for &(name, test) in tests {
+		print!("test {} ... ", name);
+		test(&video);
+		println!("ok");
+	}
+}